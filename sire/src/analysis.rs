@@ -1,7 +1,5 @@
 use rustc::mir::*;
 
-use crate::sir::*;
-
 pub fn find_loop<'tcx>(mir: &'tcx Body<'tcx>) -> Option<Vec<BasicBlock>> {
     get_loop_start(mir, BasicBlock::from_u32(0), Vec::new())
 }
@@ -14,60 +12,70 @@ fn get_loop_start<'tcx>(
     match visited.iter().enumerate().find(|(_, b)| **b == block) {
         Some((i, _)) => Some(visited.split_off(i)),
         None => {
-            let blk = mir.basic_blocks().get(block)?;
+            mir.basic_blocks().get(block)?;
             visited.push(block);
-            match blk.terminator().kind {
-                TerminatorKind::Goto { target } => get_loop_start(mir, target, visited),
-                TerminatorKind::SwitchInt { ref targets, .. } => {
-                    let mut result = None;
-                    for target in targets {
-                        result = get_loop_start(mir, *target, visited.clone());
-                        if result.is_some() {
-                            break;
-                        }
-                    }
-                    result
-                }
-                TerminatorKind::Call { destination: Some((_, target)), .. } => {
-                    get_loop_start(mir, target, visited)
-                }
-                _ => None,
-            }
+            successors(mir, block)
+                .into_iter()
+                .find_map(|target| get_loop_start(mir, target, visited.clone()))
         }
     }
 }
 
-impl Expr {
-    pub fn find_datatype_instances(&self) -> Vec<Ty> {
-        Instanced::find_types(self)
-    }
+/// Returns the immediate post-dominator of every block, which is the first block that every path
+/// from the block to the end of the function goes through. Only the edges followed by the
+/// evaluator are considered, paths ending in an `Unreachable` terminator are ignored and the body
+/// must not contain loops.
+pub fn post_dominators<'tcx>(mir: &'tcx Body<'tcx>) -> Vec<Option<BasicBlock>> {
+    let mut chains = vec![None; mir.basic_blocks().len()];
+    mir.basic_blocks()
+        .indices()
+        .map(|block| post_dominator_chain(mir, block, &mut chains)?.get(1).copied())
+        .collect()
 }
 
-#[derive(Default)]
-struct Instanced {
-    inner: Vec<Ty>,
-}
-
-impl Instanced {
-    fn find_types(expr: &Expr) -> Vec<Ty> {
-        let mut this = Self::default();
-        this.visit_expr(expr);
-        this.inner
+/// Returns the post-dominators of the block, starting with the block itself and ordered so every
+/// block post-dominates the previous one, or `None` if every path from the block is unreachable.
+fn post_dominator_chain<'tcx>(
+    mir: &'tcx Body<'tcx>,
+    block: BasicBlock,
+    chains: &mut Vec<Option<Option<Vec<BasicBlock>>>>,
+) -> Option<Vec<BasicBlock>> {
+    if let Some(chain) = &chains[block.index()] {
+        return chain.clone();
     }
-}
 
-impl Visitor for Instanced {
-    fn visit_expr(&mut self, expr: &Expr) {
-        self.super_expr(expr);
-        let ty = expr.ty();
-
-        match ty {
-            Ty::Tuple(_) => {
-                if !self.inner.contains(&ty) {
-                    self.inner.push(ty);
-                }
+    let chain = if let TerminatorKind::Unreachable = mir[block].terminator().kind {
+        None
+    } else {
+        let mut common: Option<Vec<BasicBlock>> = None;
+        let successors = successors(mir, block);
+        for &successor in &successors {
+            if let Some(chain) = post_dominator_chain(mir, successor, chains) {
+                common = Some(match common {
+                    Some(common) => common.into_iter().filter(|b| chain.contains(b)).collect(),
+                    None => chain,
+                });
             }
-            _ => (),
         }
+        match common {
+            Some(common) => Some(Some(block).into_iter().chain(common).collect()),
+            None if successors.is_empty() => Some(vec![block]),
+            None => None,
+        }
+    };
+
+    chains[block.index()] = Some(chain.clone());
+    chain
+}
+
+/// The blocks the evaluator can jump to from the given one.
+fn successors<'tcx>(mir: &'tcx Body<'tcx>, block: BasicBlock) -> Vec<BasicBlock> {
+    match mir[block].terminator().kind {
+        TerminatorKind::Goto { target }
+        | TerminatorKind::Drop { target, .. }
+        | TerminatorKind::DropAndReplace { target, .. } => vec![target],
+        TerminatorKind::SwitchInt { ref targets, .. } => targets.clone(),
+        TerminatorKind::Call { destination: Some((_, target)), .. } => vec![target],
+        _ => Vec::new(),
     }
 }
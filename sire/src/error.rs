@@ -0,0 +1,90 @@
+use std::fmt;
+
+use rustc::mir::Location;
+use syntax_pos::Span;
+
+pub type EvalResult<T = ()> = Result<T, Error>;
+
+/// An error raised while evaluating a MIR body into SIR.
+///
+/// The `location` and `span` are filled in by the evaluator for errors that can be traced back
+/// to a particular statement or terminator, so tools can point at the offending source code.
+#[derive(Clone, Debug)]
+pub struct Error {
+    pub kind: ErrorKind,
+    pub location: Option<Location>,
+    pub span: Option<Span>,
+}
+
+#[derive(Clone, Debug, PartialEq, Eq)]
+pub enum ErrorKind {
+    UnsupportedStatement(String),
+    UnsupportedTerminator(String),
+    UnsupportedRvalue(String),
+    UnsupportedConst(String),
+    UnsupportedType(String),
+    ContainsLoop,
+    InvalidPlace(String),
+    MissingBlock(String),
+    MemoryLeak,
+    /// The expected return type and the type of the value returned.
+    MismatchedReturnType(String, String),
+    Solver(String),
+}
+
+impl Error {
+    pub fn new(kind: ErrorKind) -> Self {
+        Error { kind, location: None, span: None }
+    }
+
+    /// Attaches a location and a span to the error, unless it already has them.
+    pub fn at(mut self, location: Location, span: Span) -> Self {
+        self.location = self.location.or(Some(location));
+        self.span = self.span.or(Some(span));
+        self
+    }
+
+    pub fn with_span(mut self, span: Span) -> Self {
+        self.span = self.span.or(Some(span));
+        self
+    }
+}
+
+impl From<ErrorKind> for Error {
+    fn from(kind: ErrorKind) -> Self {
+        Error::new(kind)
+    }
+}
+
+impl fmt::Display for ErrorKind {
+    fn fmt(&self, f: &mut fmt::Formatter<'_>) -> fmt::Result {
+        match self {
+            ErrorKind::UnsupportedStatement(s) => write!(f, "statement `{}` is not supported", s),
+            ErrorKind::UnsupportedTerminator(s) => {
+                write!(f, "terminator `{}` is not supported", s)
+            }
+            ErrorKind::UnsupportedRvalue(s) => write!(f, "rvalue `{}` is not supported", s),
+            ErrorKind::UnsupportedConst(s) => write!(f, "constant `{}` is not supported", s),
+            ErrorKind::UnsupportedType(s) => write!(f, "type `{}` is not supported", s),
+            ErrorKind::ContainsLoop => write!(f, "loops are not supported"),
+            ErrorKind::InvalidPlace(s) => write!(f, "place `{}` is not initialized", s),
+            ErrorKind::MissingBlock(s) => write!(f, "basic block `{}` does not exist", s),
+            ErrorKind::MemoryLeak => write!(f, "memory is not empty after execution"),
+            ErrorKind::MismatchedReturnType(expected, found) => {
+                write!(f, "the function returns `{}` but its body has type `{}`", expected, found)
+            }
+            ErrorKind::Solver(s) => write!(f, "the solver failed: {}", s),
+        }
+    }
+}
+
+impl fmt::Display for Error {
+    fn fmt(&self, f: &mut fmt::Formatter<'_>) -> fmt::Result {
+        match self.location {
+            Some(location) => write!(f, "{} at {:?}", self.kind, location),
+            None => write!(f, "{}", self.kind),
+        }
+    }
+}
+
+impl std::error::Error for Error {}
@@ -1,10 +1,15 @@
+use std::cell::{Ref, RefCell};
+use std::rc::Rc;
+
 use rustc::hir::def_id::DefId;
-use rustc::mir::interpret::{ConstValue, InterpResult};
+use rustc::mir::interpret::ConstValue;
 use rustc::mir::*;
-use rustc::ty::{self, layout::Size, TyCtxt};
-use rustc::{err_unsup, err_unsup_format};
+use rustc::ty::layout::{Size, VariantIdx};
+use rustc::ty::{self, TyCtxt};
+use sire_smt::{check_satisfiability, CheckResult, Solver};
 
-use crate::analysis::find_loop;
+use crate::analysis::{find_loop, post_dominators};
+use crate::error::*;
 use crate::sir::*;
 
 use self::memory::*;
@@ -13,118 +18,293 @@ use self::util::*;
 mod memory;
 mod util;
 
+/// The paths found by `Evaluator::explore_paths`.
+pub struct Exploration {
+    pub paths: Vec<Path>,
+    /// The conditions of the branches that were not explored because the limit of paths was
+    /// reached.
+    pub unexplored: Vec<Vec<Expr>>,
+}
+
 #[derive(Clone)]
 pub struct Evaluator<'tcx> {
     location: Location,
+    /// Block at which `run` halts, used to evaluate the branches of a `SwitchInt` up to their join.
+    stop: Option<BasicBlock>,
+    /// Conditions assumed on the path being evaluated.
+    conditions: Vec<ExprId>,
+    /// Evaluators for the other arms of every `SwitchInt` met so far, if the paths through the
+    /// function are being explored one by one.
+    branches: Option<Vec<Evaluator<'tcx>>>,
+    /// Whether the path being evaluated ended in a call that does not return, an `Abort` or a
+    /// `Resume`.
+    aborted: bool,
+    /// Whether the path being evaluated reached an `Unreachable` terminator, so its path
+    /// condition cannot hold.
+    unreachable: bool,
     memory: Memory<'tcx>,
     def_id: Option<DefId>,
+    post_dominators: Rc<Vec<Option<BasicBlock>>>,
+    /// Solver used to drop the arms of a `SwitchInt` that cannot be reached, if any.
+    solver: Option<Solver>,
+    symbols: Rc<RefCell<SymbolTable>>,
+    arena: Rc<RefCell<ExprArena>>,
     tcx: TyCtxt<'tcx>,
 }
 
 impl<'tcx> Evaluator<'tcx> {
     pub fn from_tcx(tcx: TyCtxt<'tcx>) -> Self {
-        Evaluator { location: Location::START, memory: Default::default(), def_id: None, tcx }
+        Evaluator {
+            location: Location::START,
+            stop: None,
+            conditions: Vec::new(),
+            branches: None,
+            aborted: false,
+            unreachable: false,
+            memory: Default::default(),
+            def_id: None,
+            post_dominators: Default::default(),
+            solver: None,
+            symbols: Default::default(),
+            arena: Default::default(),
+            tcx,
+        }
+    }
+
+    /// Asks the solver whether the path condition of every branch can hold before evaluating it,
+    /// leaving the branches that cannot be taken out of the resulting SIR.
+    pub fn set_solver(&mut self, solver: Solver) {
+        self.solver = Some(solver);
+    }
+
+    /// Returns the identifier used for the function with the given `DefId` in SIR.
+    pub fn func_id(&self, def_id: DefId) -> FuncId {
+        self.symbols.borrow_mut().insert(&self.tcx.def_path_str(def_id))
     }
 
-    pub fn eval_mir(&mut self, def_id: DefId) -> InterpResult<'tcx, FuncDef> {
+    /// Returns the table of every function identifier created so far.
+    pub fn symbols(&self) -> Ref<'_, SymbolTable> {
+        self.symbols.borrow()
+    }
+
+    /// Adds the node to the arena shared by this evaluator and its forks.
+    fn intern(&self, node: Node) -> ExprId {
+        self.arena.borrow_mut().intern(node)
+    }
+
+    pub fn eval_mir(&mut self, def_id: DefId) -> EvalResult<FuncDef> {
+        let (args_ty, params) = self.start(def_id)?;
+
+        self.run()?;
+
+        if self.aborted || self.unreachable {
+            // No path returns, so the locals are never freed. A function whose paths all end in
+            // `Unreachable` cannot be called, which is modelled as an abort.
+            let root = if self.unreachable {
+                self.intern(Node::Abort(args_ty[0].clone()))
+            } else {
+                self.memory.get(&Place::return_place())?
+            };
+            self.memory = Default::default();
+            self.memory.insert(Place::return_place(), root);
+        } else {
+            let mir = self.tcx.optimized_mir(def_id);
+            let locals_len = mir.local_decls.len();
+            let (_, dead) = CheckStorage::run(&mir);
+
+            for i in 1usize..args_ty.len() {
+                self.memory.remove_from_int(i)?;
+            }
+
+            for i in args_ty.len()..locals_len {
+                let local = Local::from_usize(i);
+                if !dead.contains(&local) {
+                    self.memory.remove(&local.into())?;
+                }
+            }
+        }
+
+        let root = self.memory.remove(&Place::return_place())?;
+        let span = self.tcx.def_span(def_id);
+        if let Node::Uninitialized = self.arena.borrow().node(root) {
+            let place = format!("{:?}", Place::return_place());
+            return Err(Error::new(ErrorKind::InvalidPlace(place)).with_span(span));
+        }
+        let ty = self.arena.borrow().ty(root);
+        if ty != args_ty[0] {
+            let kind = ErrorKind::MismatchedReturnType(args_ty[0].to_string(), ty.to_string());
+            return Err(Error::new(kind).with_span(span));
+        }
+
+        let body = self.optimized(root);
+
+        if self.memory.is_empty() {
+            Ok(FuncDef { body, id: self.func_id(def_id), ty: Ty::Func(args_ty.clone(), params) })
+        } else {
+            Err(Error::new(ErrorKind::MemoryLeak).with_span(span))
+        }
+    }
+
+    /// Prepares the evaluation of the function with the given `DefId`, returning the types of
+    /// its return value and arguments, and its const parameters.
+    fn start(&mut self, def_id: DefId) -> EvalResult<(Vec<Ty>, Vec<Param>)> {
         let mir = self.tcx.optimized_mir(def_id);
 
-        if find_loop(mir).is_some() {
-            return Err(err_unsup_format!("The function {:?} contains loops", def_id).into());
+        // A previous evaluation might have failed halfway, leaving stale state behind.
+        self.memory = Default::default();
+        self.location = Location::START;
+        self.stop = None;
+        self.conditions.clear();
+        self.branches = None;
+        self.aborted = false;
+        self.unreachable = false;
+        *self.arena.borrow_mut() = ExprArena::default();
+
+        if let Some(blocks) = find_loop(mir) {
+            // The last block of the cycle is the one jumping back to the start of the loop.
+            let block = *blocks.last().unwrap();
+            let location = Location { block, statement_index: mir[block].statements.len() };
+            return Err(Error::new(ErrorKind::ContainsLoop)
+                .at(location, mir[block].terminator().source_info.span));
         }
 
+        self.post_dominators = Rc::new(post_dominators(mir));
+
         let args_ty = mir
             .local_decls
             .iter()
             .take(mir.arg_count + 1)
-            .map(|ld| self.transl_ty(&ld.ty))
-            .collect::<InterpResult<'_, Vec<Ty>>>()?;
+            .map(|ld| self.transl_ty(&ld.ty).map_err(|err| err.with_span(ld.source_info.span)))
+            .collect::<EvalResult<Vec<Ty>>>()?;
 
-        self.memory.insert(Place::return_place(), Expr::Uninitialized);
+        let uninitialized = self.intern(Node::Uninitialized);
+        self.memory.insert(Place::return_place(), uninitialized);
 
         for (i, arg_ty) in args_ty.iter().enumerate().skip(1) {
-            self.memory.insert_from_int(i, Expr::Value(Value::Arg(i, arg_ty.clone())));
+            let arg = self.intern(Node::Value(Value::Arg(i, arg_ty.clone())));
+            self.memory.insert_from_int(i, arg);
         }
 
         let params = ExtractParams::run(self, &mir);
 
         let locals_len = mir.local_decls.len();
-        let (live, dead) = CheckStorage::run(&mir);
+        let (live, _) = CheckStorage::run(&mir);
 
         for i in args_ty.len()..locals_len {
             let local = Local::from_usize(i);
             if !live.contains(&local) {
-                self.memory.insert_from_int(i, Expr::Uninitialized);
+                self.memory.insert_from_int(i, uninitialized);
             }
         }
 
         self.def_id = Some(def_id);
 
-        self.run()?;
-
-        for i in 1usize..args_ty.len() {
-            self.memory.remove_from_int(i)?;
-        }
+        Ok((args_ty, params))
+    }
 
-        for i in args_ty.len()..locals_len {
-            let local = Local::from_usize(i);
-            if !dead.contains(&local) {
-                self.memory.remove(&local.into())?;
+    /// Evaluates every path through the function with the given `DefId` on its own, instead of
+    /// merging them into a single expression. Each path comes with the conditions under which it
+    /// is taken. The paths ending in an `Unreachable` terminator and the ones the solver finds
+    /// infeasible, if there is one, are left out.
+    ///
+    /// The number of paths grows exponentially with the number of branches, so the exploration
+    /// stops after `limit` paths and returns the branches it did not take.
+    pub fn explore_paths(&mut self, def_id: DefId, limit: usize) -> EvalResult<Exploration> {
+        self.start(def_id)?;
+
+        self.branches = Some(Vec::new());
+        let mut pending = vec![self.clone()];
+        self.branches = None;
+
+        let mut paths = Vec::new();
+        while paths.len() < limit {
+            let mut evaluator = match pending.pop() {
+                Some(evaluator) => evaluator,
+                None => break,
+            };
+            evaluator.run()?;
+            pending.extend(evaluator.branches.take().unwrap_or_default());
+            if evaluator.unreachable {
+                continue;
             }
-        }
 
-        let mut body = self.memory.remove(&Place::return_place())?;
+            paths.push(Path {
+                conditions: self.optimized_conditions(&evaluator),
+                result: self.optimized(evaluator.memory.get(&Place::return_place())?),
+            });
+        }
 
-        body.optimize();
+        let unexplored = pending.iter().map(|branch| self.optimized_conditions(branch)).collect();
+        Ok(Exploration { paths, unexplored })
+    }
 
-        assert_eq!(args_ty[0], body.ty());
+    /// Simplifies the expression in the arena, so shared subexpressions stay shared.
+    fn optimized(&self, id: ExprId) -> Expr {
+        let id = self.arena.borrow_mut().optimize(id);
+        self.arena.borrow().expr(id)
+    }
 
-        if self.memory.is_empty() {
-            Ok(FuncDef { body, def_id, ty: Ty::Func(args_ty.clone(), params) })
-        } else {
-            Err(err_unsup_format!("Memory is not empty after execution").into())
-        }
+    fn optimized_conditions(&self, evaluator: &Evaluator<'tcx>) -> Vec<Expr> {
+        evaluator.conditions.iter().map(|&id| self.optimized(id)).collect()
     }
 
-    fn run(&mut self) -> InterpResult<'tcx> {
-        while self.step()? {}
+    fn run(&mut self) -> EvalResult {
+        while Some(self.location.block) != self.stop && self.step()? {}
         Ok(())
     }
 
-    fn step(&mut self) -> InterpResult<'tcx, bool> {
+    fn step(&mut self) -> EvalResult<bool> {
+        let location = self.location;
         let block_data = self
             .tcx
             .optimized_mir(self.def_id.expect("Bug: DefId should be some"))
             .basic_blocks()
-            .get(self.location.block)
-            .ok_or_else(|| err_unsup_format!("Basic block not found"))?;
-
-        match block_data.statements.get(self.location.statement_index) {
-            Some(statement) => self.eval_statement(statement),
-            None => self.eval_terminator(block_data.terminator()),
+            .get(location.block)
+            .ok_or_else(|| Error::new(ErrorKind::MissingBlock(format!("{:?}", location.block))))?;
+
+        match block_data.statements.get(location.statement_index) {
+            Some(statement) => self
+                .eval_statement(statement)
+                .map_err(|err| err.at(location, statement.source_info.span)),
+            None => {
+                let terminator = block_data.terminator();
+                self.eval_terminator(terminator)
+                    .map_err(|err| err.at(location, terminator.source_info.span))
+            }
         }
     }
 
-    fn eval_statement(&mut self, statement: &Statement<'tcx>) -> InterpResult<'tcx, bool> {
+    fn eval_statement(&mut self, statement: &Statement<'tcx>) -> EvalResult<bool> {
         match statement.kind {
             StatementKind::Assign(box (ref place, ref rvalue)) => {
                 self.eval_rvalue_into_place(rvalue, place)?;
             }
             StatementKind::StorageLive(local) => {
-                self.memory.insert(local.into(), Expr::Uninitialized);
+                let uninitialized = self.intern(Node::Uninitialized);
+                self.memory.insert(local.into(), uninitialized);
             }
             StatementKind::StorageDead(local) => {
                 self.memory.remove(&local.into())?;
             }
+            // Only fieldless enums are supported, which are represented by their discriminant.
+            StatementKind::SetDiscriminant { ref place, variant_index } => {
+                let discr = self.discriminant(place, variant_index)?;
+                *self.memory.get_mut(place)? = discr;
+            }
+            // These only matter to the borrow checker and to Miri, so they do nothing here.
+            StatementKind::FakeRead(..)
+            | StatementKind::Retag(..)
+            | StatementKind::AscribeUserType(..)
+            | StatementKind::Nop => (),
             ref sk => {
-                return Err(err_unsup_format!("StatementKind {:?} is unsupported", sk).into());
+                return Err(ErrorKind::UnsupportedStatement(format!("{:?}", sk)).into());
             }
         };
         self.location = self.location.successor_within_block();
         Ok(true)
     }
 
-    fn eval_terminator(&mut self, terminator: &Terminator<'tcx>) -> InterpResult<'tcx, bool> {
+    fn eval_terminator(&mut self, terminator: &Terminator<'tcx>) -> EvalResult<bool> {
         match terminator.kind {
             TerminatorKind::Return => {
                 self.location = Location::START;
@@ -141,37 +321,174 @@ impl<'tcx> Evaluator<'tcx> {
                     for op in args {
                         args_expr.push(self.eval_operand(op)?);
                     }
-                    *self.memory.get_mut(place)? = Expr::Apply(Box::new(func_expr), args_expr);
+                    let call = self.intern(Node::Apply(func_expr, args_expr));
+                    *self.memory.get_mut(place)? = call;
                     self.location = block.start_location();
                     Ok(true)
                 }
-                None => Err(err_unsup_format!("Call terminator does not assign").into()),
+                // The call diverges, so the function aborts.
+                None => self.abort(),
             },
+            TerminatorKind::Abort | TerminatorKind::Resume => self.abort(),
+            // The path condition cannot hold, so the result of this path does not matter.
+            TerminatorKind::Unreachable => {
+                self.unreachable = true;
+                self.location = Location::START;
+                Ok(false)
+            }
+            // Only types without drop glue are supported, so dropping them does nothing.
+            TerminatorKind::Drop { ref location, target, .. } => {
+                self.check_no_drop_glue(location)?;
+                self.location = target.start_location();
+                Ok(true)
+            }
+            TerminatorKind::DropAndReplace { ref location, ref value, target, .. } => {
+                self.check_no_drop_glue(location)?;
+                let value = self.eval_operand(value)?;
+                *self.memory.get_mut(location)? = value;
+                self.location = target.start_location();
+                Ok(true)
+            }
             TerminatorKind::SwitchInt {
                 ref discr, ref switch_ty, ref values, ref targets, ..
             } => {
                 let discr_expr = self.eval_operand(&discr)?;
-                let mut values_expr = Vec::new();
-                let mut targets_expr = Vec::new();
+                let switch_ty = self.transl_ty(switch_ty)?;
+
+                let values_expr = values
+                    .iter()
+                    .map(|&bytes| self.intern(Node::Value(Value::Const(bytes, switch_ty.clone()))))
+                    .collect::<Vec<_>>();
 
-                for (&bytes, &block) in values.iter().zip(targets) {
-                    let mut target_expr = self.fork_eval(block)?;
+                let mut arms = Vec::new();
+                for (&value, &target) in values_expr.iter().zip(targets) {
+                    let condition = self.intern(Node::BinaryOp(BinOp::Eq, discr_expr, value));
+                    arms.push((Some(value), target, vec![condition]));
+                }
+                let otherwise = values_expr
+                    .iter()
+                    .map(|&value| self.intern(Node::BinaryOp(BinOp::Ne, discr_expr, value)))
+                    .collect();
+                arms.push((None, *targets.last().unwrap(), otherwise));
+
+                // Arms contradicting the path condition are never taken and conditions that
+                // already hold add nothing to it.
+                let mut arms = arms
+                    .into_iter()
+                    .filter(|(_, _, conditions)| {
+                        conditions.iter().all(|&condition| self.implied(condition) != Some(false))
+                    })
+                    .map(|(value, target, conditions)| {
+                        let conditions = conditions
+                            .into_iter()
+                            .filter(|&condition| self.implied(condition) != Some(true))
+                            .collect::<Vec<_>>();
+                        (value, target, conditions)
+                    })
+                    .collect::<Vec<_>>();
+
+                if arms.len() > 1 {
+                    let mut feasible = Vec::new();
+                    for (value, target, conditions) in arms.iter().cloned() {
+                        if self.is_feasible(&conditions)? {
+                            feasible.push((value, target, conditions));
+                        }
+                    }
+                    // Every arm is infeasible only if this path is, so its result does not matter.
+                    if !feasible.is_empty() {
+                        arms = feasible;
+                    }
+                }
+
+                // When exploring paths, the other arms are left to be evaluated on their own.
+                if arms.len() == 1 || self.branches.is_some() {
+                    let (_, target, conditions) = arms.pop().unwrap();
+                    for (_, target, conditions) in arms {
+                        let mut branch = self.clone();
+                        branch.branches = Some(Vec::new());
+                        branch.conditions.extend(conditions);
+                        branch.location = target.start_location();
+                        self.branches.as_mut().unwrap().push(branch);
+                    }
+                    self.conditions.extend(conditions);
+                    self.location = target.start_location();
+                    return Ok(true);
+                }
 
-                    let value_expr = Expr::Value(Value::Const(bytes, self.transl_ty(switch_ty)?));
+                // Branches that meet again are evaluated up to the join point and their memories
+                // merged, so the code after it is evaluated once. Branches that reach an
+                // `Unreachable` terminator are left out.
+                if let Some(join) = self.post_dominators[self.location.block.index()] {
+                    let mut arm_values = Vec::new();
+                    let mut arm_conditions = Vec::new();
+                    let mut memories = Vec::new();
+                    for (value, target, conditions) in arms {
+                        let fork = self.fork(target, Some(join), conditions.clone())?;
+                        if !fork.unreachable {
+                            arm_values.push(value);
+                            arm_conditions.push(conditions);
+                            memories.push(fork.memory);
+                        }
+                    }
+                    if memories.is_empty() {
+                        self.unreachable = true;
+                        self.location = Location::START;
+                        return Ok(false);
+                    }
 
-                    target_expr.replace(&discr_expr, &value_expr);
+                    self.memory = Memory::merge(memories, |targets_expr| {
+                        if targets_expr.iter().all(|&target| target == targets_expr[0]) {
+                            targets_expr[0]
+                        } else {
+                            self.switch(discr_expr, &arm_values, targets_expr)
+                        }
+                    });
+
+                    // The rest of the path can assume that one of the merged arms was taken,
+                    // which says something only if some arms were left out.
+                    if arm_conditions.len() < targets.len() {
+                        match arm_conditions.as_slice() {
+                            [conditions] => self.conditions.extend(conditions),
+                            _ => {
+                                let disjunction = self.disjunction(&arm_conditions);
+                                self.conditions.extend(disjunction);
+                            }
+                        }
+                    }
 
-                    values_expr.push(value_expr);
-                    targets_expr.push(target_expr);
+                    self.location = join.start_location();
+                    return Ok(true);
                 }
 
-                self.location = targets.last().unwrap().start_location();
-                self.run()?;
+                let mut arm_values = Vec::new();
+                let mut targets_expr = Vec::new();
+                let mut returned = None;
+                for (value, target, conditions) in arms {
+                    let fork = self.fork(target, None, conditions)?;
+                    if fork.unreachable {
+                        continue;
+                    }
+                    arm_values.push(value);
+                    targets_expr.push(fork.memory.get(&Place::return_place())?);
+                    if !fork.aborted && returned.is_none() {
+                        returned = Some(fork.memory);
+                    }
+                }
+
+                if targets_expr.is_empty() {
+                    self.unreachable = true;
+                    self.location = Location::START;
+                    return Ok(false);
+                }
 
-                targets_expr.push(self.memory.get(&Place::return_place())?.clone());
+                // The locals are freed as in any branch that returns.
+                match returned {
+                    Some(memory) => self.memory = memory,
+                    None => self.aborted = true,
+                }
 
-                *self.memory.get_mut(&Place::return_place())? =
-                    Expr::Switch(Box::new(discr_expr), values_expr, targets_expr);
+                let switch = self.switch(discr_expr, &arm_values, targets_expr);
+                *self.memory.get_mut(&Place::return_place())? = switch;
 
                 self.location = Location::START;
                 Ok(false)
@@ -193,7 +510,7 @@ impl<'tcx> Evaluator<'tcx> {
             //     self.location = Location::START;
             //     Ok(false)
             // }
-            ref tk => Err(err_unsup_format!("TerminatorKind {:?} is not supported", tk).into()),
+            ref tk => Err(ErrorKind::UnsupportedTerminator(format!("{:?}", tk)).into()),
         }
     }
 
@@ -201,25 +518,27 @@ impl<'tcx> Evaluator<'tcx> {
         &mut self,
         rvalue: &Rvalue<'tcx>,
         place: &Place<'tcx>,
-    ) -> InterpResult<'tcx> {
+    ) -> EvalResult {
+        let unsupported = || ErrorKind::UnsupportedRvalue(format!("{:?}", rvalue));
         let value = match rvalue {
-            Rvalue::BinaryOp(bin_op, op1, op2) => Expr::BinaryOp(
-                *bin_op,
-                Box::new(self.eval_operand(op1)?),
-                Box::new(self.eval_operand(op2)?),
-            ),
-            Rvalue::CheckedBinaryOp(bin_op, op1, op2) => Expr::Tuple(vec![
-                Expr::BinaryOp(
-                    *bin_op,
-                    Box::new(self.eval_operand(op1)?),
-                    Box::new(self.eval_operand(op2)?),
-                ),
+            Rvalue::BinaryOp(bin_op, op1, op2) => {
+                let bin_op = transl_bin_op(*bin_op).ok_or_else(unsupported)?;
+                let (e1, e2) = (self.eval_operand(op1)?, self.eval_operand(op2)?);
+                self.intern(Node::BinaryOp(bin_op, e1, e2))
+            }
+            Rvalue::CheckedBinaryOp(bin_op, op1, op2) => {
+                let bin_op = transl_bin_op(*bin_op).ok_or_else(unsupported)?;
+                let (e1, e2) = (self.eval_operand(op1)?, self.eval_operand(op2)?);
+                let result = self.intern(Node::BinaryOp(bin_op, e1, e2));
                 // FIXME: Check the operation
-                Expr::Value(Value::Const(0, Ty::Bool)),
-            ]),
-            Rvalue::Ref(_, BorrowKind::Shared, place) => self.memory.get(place)?.clone(),
+                let overflow = self.intern(Node::Value(Value::Const(0, Ty::Bool)));
+                self.intern(Node::Tuple(vec![result, overflow]))
+            }
+            Rvalue::Ref(_, BorrowKind::Shared, place) => self.memory.get(place)?,
+            // Fieldless enums are already stored as their discriminant.
+            Rvalue::Discriminant(place) => self.memory.get(place)?,
             Rvalue::Use(op) => self.eval_operand(op)?,
-            ref rv => return Err(err_unsup_format!("Rvalue {:?} unsupported", rv).into()),
+            _ => return Err(unsupported().into()),
         };
 
         *self.memory.get_mut(place)? = value;
@@ -227,14 +546,13 @@ impl<'tcx> Evaluator<'tcx> {
         Ok(())
     }
 
-    fn eval_operand(&self, operand: &Operand<'tcx>) -> InterpResult<'tcx, Expr> {
+    fn eval_operand(&self, operand: &Operand<'tcx>) -> EvalResult<ExprId> {
         Ok(match operand {
             Operand::Move(Place { base, projection })
             | Operand::Copy(Place { base, projection }) => {
-                let expr =
-                    self.memory.get(&Place { base: base.clone(), projection: box [] })?.clone();
+                let expr = self.memory.get(&Place { base: base.clone(), projection: box [] })?;
                 if let box [.., ProjectionElem::Field(field, _)] = projection {
-                    Expr::Projection(Box::new(expr), field.index())
+                    self.intern(Node::Projection(expr, field.index()))
                 } else {
                     expr
                 }
@@ -243,32 +561,33 @@ impl<'tcx> Evaluator<'tcx> {
             Operand::Constant(constant) => {
                 let const_ty = &constant.literal.ty;
                 let ty = self.transl_ty(const_ty)?;
-                Expr::Value(match ty {
+                let value = match ty {
                     Ty::Func(_, _) => match const_ty.kind {
-                        ty::FnDef(def_id, _) => Value::Function(def_id, ty),
+                        ty::FnDef(def_id, _) => Value::Function(self.func_id(def_id), ty),
                         _ => unreachable!(),
                     },
 
                     _ => match constant.literal.val {
                         ConstValue::Scalar(scalar) => Value::Const(
-                            scalar.to_bits(Size::from_bits(ty.bits().unwrap() as u64))?,
+                            scalar.to_bits(Size::from_bits(ty.bits().unwrap() as u64)).map_err(
+                                |_| ErrorKind::UnsupportedConst(format!("{:?}", scalar)),
+                            )?,
                             ty,
                         ),
                         ConstValue::Param(param) => {
                             Value::ConstParam(Param(param.index as usize, ty))
                         }
                         val => {
-                            return Err(
-                                err_unsup_format!("Unsupported ConstValue: {:?}", val).into()
-                            );
+                            return Err(ErrorKind::UnsupportedConst(format!("{:?}", val)).into());
                         }
                     },
-                })
+                };
+                self.intern(Node::Value(value))
             }
         })
     }
     #[allow(rustc::usage_of_qualified_ty)]
-    fn transl_ty(&self, ty: ty::Ty<'tcx>) -> InterpResult<'tcx, Ty> {
+    fn transl_ty(&self, ty: ty::Ty<'tcx>) -> EvalResult<Ty> {
         match ty.kind {
             ty::Bool => Ok(Ty::Bool),
             ty::Int(int_ty) => {
@@ -277,28 +596,178 @@ impl<'tcx> Evaluator<'tcx> {
             ty::Uint(uint_ty) => {
                 Ok(Ty::Uint(uint_ty.bit_width().unwrap_or(8 * std::mem::size_of::<usize>())))
             }
-            ty::FnDef(def_id, _) => self
-                .tcx
-                .optimized_mir(def_id)
-                .local_decls
-                .iter()
-                .map(|ld| self.transl_ty(&ld.ty))
-                .collect::<InterpResult<'_, Vec<Ty>>>()
-                .map(|args_ty| Ty::Func(args_ty, Vec::new())),
-            _ => Err(err_unsup_format!("Unsupported ty {:?}", ty).into()),
+            ty::FnDef(def_id, _) => {
+                let mir = self.tcx.optimized_mir(def_id);
+                // Only the return place and the arguments, the other locals are not part of the
+                // signature.
+                mir.local_decls
+                    .iter()
+                    .take(mir.arg_count + 1)
+                    .map(|ld| self.transl_ty(&ld.ty))
+                    .collect::<EvalResult<Vec<Ty>>>()
+                    .map(|args_ty| Ty::Func(args_ty, Vec::new()))
+            }
+            _ => Err(ErrorKind::UnsupportedType(format!("{:?}", ty)).into()),
         }
     }
 
-    fn fork_eval(&self, block: BasicBlock) -> InterpResult<'tcx, Expr> {
+    /// Returns the discriminant of the given variant of the enum stored in the place, which must
+    /// have no fields.
+    fn discriminant(&self, place: &Place<'tcx>, variant: VariantIdx) -> EvalResult<ExprId> {
+        let def_id = self.def_id.unwrap();
+        let ty = place.ty(self.tcx.optimized_mir(def_id), self.tcx).ty;
+        let unsupported = || ErrorKind::UnsupportedType(format!("{:?}", ty));
+        match ty.kind {
+            ty::Adt(adt_def, _) if adt_def.variants.iter().all(|v| v.fields.is_empty()) => (),
+            _ => return Err(unsupported().into()),
+        }
+        let discr = ty.discriminant_for_variant(self.tcx, variant).ok_or_else(unsupported)?;
+        let discr_ty = self.transl_ty(discr.ty)?;
+        Ok(self.intern(Node::Value(Value::Const(discr.val, discr_ty))))
+    }
+
+    /// Ends the path being evaluated with a failure.
+    fn abort(&mut self) -> EvalResult<bool> {
+        let mir = self.tcx.optimized_mir(self.def_id.unwrap());
+        let ty = self.transl_ty(mir.local_decls[RETURN_PLACE].ty)?;
+        let abort = self.intern(Node::Abort(ty));
+        *self.memory.get_mut(&Place::return_place())? = abort;
+        self.aborted = true;
+        self.location = Location::START;
+        Ok(false)
+    }
+
+    /// Fails unless dropping the place does nothing, which is the case for the types that have
+    /// no drop glue.
+    fn check_no_drop_glue(&self, place: &Place<'tcx>) -> EvalResult {
+        let def_id = self.def_id.unwrap();
+        let ty = place.ty(self.tcx.optimized_mir(def_id), self.tcx).ty;
+        if ty.needs_drop(self.tcx, self.tcx.param_env(def_id)) {
+            return Err(ErrorKind::UnsupportedTerminator(format!("drop of {:?}", ty)).into());
+        }
+        Ok(())
+    }
+
+    /// Evaluates a copy of this evaluator from the start of `block` until it reaches `stop` or
+    /// returns. The fork assumes `conditions` on top of the current path condition.
+    fn fork(
+        &self,
+        block: BasicBlock,
+        stop: Option<BasicBlock>,
+        conditions: Vec<ExprId>,
+    ) -> EvalResult<Evaluator<'tcx>> {
         let mut fork = Evaluator {
-            memory: self.memory.clone(),
             location: block.start_location(),
+            stop,
+            conditions: self.conditions.iter().copied().chain(conditions).collect(),
+            branches: None,
+            aborted: false,
+            unreachable: false,
+            memory: self.memory.clone(),
             def_id: self.def_id,
+            post_dominators: self.post_dominators.clone(),
+            solver: self.solver,
+            symbols: self.symbols.clone(),
+            arena: self.arena.clone(),
             tcx: self.tcx,
         };
 
         fork.run()?;
 
-        fork.memory.get(&Place::return_place()).map(|e| e.clone())
+        Ok(fork)
+    }
+
+    /// Checks with the solver, if there is one, whether the path condition can hold together with
+    /// the given conditions. Conditions the solver cannot decide are assumed to hold.
+    fn is_feasible(&self, conditions: &[ExprId]) -> EvalResult<bool> {
+        let solver = match self.solver {
+            Some(solver) => solver,
+            None => return Ok(true),
+        };
+        let conditions = self.conditions.iter().chain(conditions).copied().collect::<Vec<_>>();
+        let result = check_satisfiability(&self.arena.borrow(), &conditions, solver)
+            .map_err(|err| ErrorKind::Solver(err.to_string()))?;
+        match result {
+            CheckResult::Sat | CheckResult::Undecided => Ok(true),
+            CheckResult::Unsat => Ok(false),
+            CheckResult::Unknown(output) => {
+                Err(ErrorKind::Solver(format!("unexpected output: {}", output)).into())
+            }
+        }
+    }
+
+    /// Decides `condition` from the path condition when it is a comparison of an expression with
+    /// a constant that the path already compared with a constant.
+    fn implied(&self, condition: ExprId) -> Option<bool> {
+        let arena = self.arena.borrow();
+        let comparison = |id| match *arena.node(id) {
+            Node::BinaryOp(bin_op, e1, e2) if bin_op == BinOp::Eq || bin_op == BinOp::Ne => {
+                match arena.node(e2) {
+                    Node::Value(Value::Const(..)) => Some((bin_op, e1, e2)),
+                    _ => None,
+                }
+            }
+            _ => None,
+        };
+
+        let (bin_op, expr, value) = comparison(condition)?;
+        self.conditions.iter().find_map(|&known| {
+            let (known_op, known_expr, known_value) = comparison(known)?;
+            if known_expr != expr {
+                return None;
+            }
+            // Constants of the same type are the same node when they are equal.
+            match (known_op, bin_op) {
+                (BinOp::Eq, BinOp::Eq) => Some(known_value == value),
+                (BinOp::Eq, _) => Some(known_value != value),
+                (_, BinOp::Eq) if known_value == value => Some(false),
+                (_, BinOp::Ne) if known_value == value => Some(true),
+                _ => None,
+            }
+        })
+    }
+
+    /// Returns a condition that holds if all the conditions of one of the given lists hold, or
+    /// `None` if one of the lists is empty, so the condition always holds.
+    fn disjunction(&self, conjunctions: &[Vec<ExprId>]) -> Option<ExprId> {
+        let true_expr = self.intern(Node::Value(Value::Const(1, Ty::Bool)));
+        let false_expr = self.intern(Node::Value(Value::Const(0, Ty::Bool)));
+        let mut disjuncts = Vec::new();
+        for conditions in conjunctions {
+            // `a && b` is `if a { b } else { false }`.
+            let conjunction = conditions.iter().rev().fold(None, |rest, &condition| {
+                Some(match rest {
+                    Some(rest) => {
+                        let results = vec![false_expr, rest];
+                        self.intern(Node::Switch(condition, vec![false_expr], results))
+                    }
+                    None => condition,
+                })
+            })?;
+            disjuncts.push(conjunction);
+        }
+        // `a || b` is `if a { true } else { b }`.
+        disjuncts.into_iter().rev().fold(None, |rest, disjunct| {
+            Some(match rest {
+                Some(rest) => {
+                    self.intern(Node::Switch(disjunct, vec![false_expr], vec![rest, true_expr]))
+                }
+                None => disjunct,
+            })
+        })
+    }
+
+    /// Builds the switch choosing between the results of the feasible arms of a `SwitchInt`. The
+    /// last arm is the `otherwise` one, or takes its place if it was infeasible.
+    ///
+    /// The path condition is not attached to the arms: each arm is selected by the comparison its
+    /// condition came from, and the conditions of the enclosing branches are the ones of the
+    /// `Switch` nodes around it, so `Expr::paths` recovers the same conditions from the result.
+    fn switch(&self, discr: ExprId, values: &[Option<ExprId>], targets: Vec<ExprId>) -> ExprId {
+        if let [target] = targets[..] {
+            return target;
+        }
+        let values = values[..values.len() - 1].iter().map(|value| value.unwrap()).collect();
+        self.intern(Node::Switch(discr, values, targets))
     }
 }
@@ -1,15 +1,14 @@
 #![allow(rustc::default_hash_types)]
 use std::collections::HashMap;
 
-use rustc::mir::interpret::InterpResult;
 use rustc::mir::*;
-use rustc::{err_unsup, err_unsup_format};
 
+use crate::error::*;
 use crate::sir::*;
 
 #[derive(Default, Clone)]
 pub struct Memory<'tcx> {
-    map: HashMap<Place<'tcx>, Expr>,
+    map: HashMap<Place<'tcx>, ExprId>,
 }
 
 impl<'tcx> Memory<'tcx> {
@@ -17,33 +16,51 @@ impl<'tcx> Memory<'tcx> {
         self.map.is_empty()
     }
 
-    pub fn get(&self, place: &Place<'tcx>) -> InterpResult<'tcx, &Expr> {
+    pub fn get(&self, place: &Place<'tcx>) -> EvalResult<ExprId> {
         self.map
             .get(place)
-            .ok_or_else(|| err_unsup_format!("Cannot get from place {:?}", place).into())
+            .copied()
+            .ok_or_else(|| ErrorKind::InvalidPlace(format!("{:?}", place)).into())
     }
 
-    pub fn get_mut(&mut self, place: &Place<'tcx>) -> InterpResult<'tcx, &mut Expr> {
+    pub fn get_mut(&mut self, place: &Place<'tcx>) -> EvalResult<&mut ExprId> {
         self.map
             .get_mut(place)
-            .ok_or_else(|| err_unsup_format!("Cannot get from place {:?}", place).into())
+            .ok_or_else(|| ErrorKind::InvalidPlace(format!("{:?}", place)).into())
     }
 
-    pub fn insert(&mut self, place: Place<'tcx>, expr: Expr) {
+    pub fn insert(&mut self, place: Place<'tcx>, expr: ExprId) {
         self.map.insert(place, expr);
     }
 
-    pub fn insert_from_int(&mut self, int: usize, expr: Expr) {
+    pub fn insert_from_int(&mut self, int: usize, expr: ExprId) {
         self.insert(Local::from_usize(int).into(), expr)
     }
 
-    pub fn remove(&mut self, place: &Place<'tcx>) -> InterpResult<'tcx, Expr> {
-        self.map
-            .remove(place)
-            .ok_or_else(|| err_unsup_format!("Cannot remove from place {:?}", place).into())
+    pub fn remove(&mut self, place: &Place<'tcx>) -> EvalResult<ExprId> {
+        self.map.remove(place).ok_or_else(|| ErrorKind::InvalidPlace(format!("{:?}", place)).into())
+    }
+
+    /// Joins the memories of several branches, combining the values of every place with `merge`.
+    /// Places missing from some branch are dropped.
+    pub fn merge(
+        memories: Vec<Memory<'tcx>>,
+        mut merge: impl FnMut(Vec<ExprId>) -> ExprId,
+    ) -> Memory<'tcx> {
+        let mut map = HashMap::new();
+        if let Some(first) = memories.first() {
+            for place in first.map.keys() {
+                let ids: Option<Vec<_>> =
+                    memories.iter().map(|memory| memory.map.get(place).copied()).collect();
+                if let Some(ids) = ids {
+                    map.insert(place.clone(), merge(ids));
+                }
+            }
+        }
+        Memory { map }
     }
 
-    pub fn remove_from_int(&mut self, int: usize) -> InterpResult<'tcx, Expr> {
+    pub fn remove_from_int(&mut self, int: usize) -> EvalResult<ExprId> {
         self.remove(&Local::from_usize(int).into())
     }
 }
@@ -2,11 +2,33 @@ use std::collections::BTreeSet;
 
 use rustc::mir::interpret::ConstValue;
 use rustc::mir::visit::Visitor;
-use rustc::mir::*;
+use rustc::mir::{self, *};
 use rustc::ty::{Const, ParamConst};
 
 use crate::eval::Evaluator;
-use crate::sir::Param;
+use crate::sir::{BinOp, Param};
+
+pub fn transl_bin_op(bin_op: mir::BinOp) -> Option<BinOp> {
+    Some(match bin_op {
+        mir::BinOp::Add => BinOp::Add,
+        mir::BinOp::Sub => BinOp::Sub,
+        mir::BinOp::Mul => BinOp::Mul,
+        mir::BinOp::Div => BinOp::Div,
+        mir::BinOp::Rem => BinOp::Rem,
+        mir::BinOp::BitXor => BinOp::BitXor,
+        mir::BinOp::BitAnd => BinOp::BitAnd,
+        mir::BinOp::BitOr => BinOp::BitOr,
+        mir::BinOp::Shl => BinOp::Shl,
+        mir::BinOp::Shr => BinOp::Shr,
+        mir::BinOp::Eq => BinOp::Eq,
+        mir::BinOp::Lt => BinOp::Lt,
+        mir::BinOp::Le => BinOp::Le,
+        mir::BinOp::Ne => BinOp::Ne,
+        mir::BinOp::Ge => BinOp::Ge,
+        mir::BinOp::Gt => BinOp::Gt,
+        mir::BinOp::Offset => return None,
+    })
+}
 
 #[derive(Default)]
 pub struct CheckStorage {
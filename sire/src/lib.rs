@@ -2,6 +2,10 @@
 #![feature(slice_patterns)]
 #![feature(box_syntax)]
 
+pub use sire_sir as sir;
+
+pub use self::error::*;
+
 pub mod analysis;
 pub mod eval;
-pub mod sir;
+mod error;
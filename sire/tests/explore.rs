@@ -0,0 +1,86 @@
+#![feature(rustc_private)]
+
+extern crate rustc;
+extern crate rustc_driver;
+extern crate rustc_interface;
+
+use std::process::Command;
+
+use rustc::hir::ItemKind;
+use rustc_driver::{run_compiler, Callbacks, Compilation};
+use rustc_interface::interface;
+
+use sire::eval::Evaluator;
+
+/// Three independent branches, which give eight paths.
+const BRANCHES: &str = "
+pub fn count(x: u8, y: u8, z: u8) -> u8 {
+    let mut n = 0;
+    if x > 10 {
+        n += 1;
+    }
+    if y > 10 {
+        n += 1;
+    }
+    if z > 10 {
+        n += 1;
+    }
+    n
+}";
+
+struct Explore {
+    limit: usize,
+    /// The number of explored paths and of unexplored branches of the first function.
+    result: Option<(usize, usize)>,
+}
+
+impl Callbacks for Explore {
+    fn after_analysis(&mut self, compiler: &interface::Compiler) -> Compilation {
+        compiler.session().abort_if_errors();
+        let limit = self.limit;
+        self.result = compiler.global_ctxt().unwrap().peek_mut().enter(|tcx| {
+            let hir = tcx.hir();
+            hir.krate().items.iter().find_map(|(&hir_id, item)| match item.node {
+                ItemKind::Fn(..) => {
+                    let def_id = hir.local_def_id(hir_id);
+                    let exploration =
+                        Evaluator::from_tcx(tcx).explore_paths(def_id, limit).unwrap();
+                    Some((exploration.paths.len(), exploration.unexplored.len()))
+                }
+                _ => None,
+            })
+        });
+        Compilation::Stop
+    }
+}
+
+fn explore(code: &str, limit: usize) -> (usize, usize) {
+    let file = std::env::temp_dir().join("sire_explore.rs");
+    std::fs::write(&file, code).unwrap();
+
+    let sysroot = std::env::var("SIRE_SYSROOT").unwrap_or_else(|_| {
+        let output = Command::new("rustc").args(&["--print", "sysroot"]).output().unwrap();
+        String::from_utf8(output.stdout).unwrap().trim().to_owned()
+    });
+    let args = vec![
+        "rustc".to_owned(),
+        file.to_string_lossy().into_owned(),
+        "--crate-type=lib".to_owned(),
+        "-Copt-level=3".to_owned(),
+        "--sysroot".to_owned(),
+        sysroot,
+    ];
+
+    let mut callbacks = Explore { limit, result: None };
+    run_compiler(&args, &mut callbacks, None, None).unwrap();
+    callbacks.result.unwrap()
+}
+
+#[test]
+fn test_path_limit() {
+    assert_eq!(explore(BRANCHES, 256), (8, 0));
+
+    let (paths, unexplored) = explore(BRANCHES, 5);
+    assert_eq!(paths, 5);
+    assert!(unexplored > 0);
+}
@@ -0,0 +1,180 @@
+//! Runs sire over the crates of the current cargo workspace.
+//!
+//! When invoked as `cargo sire`, this binary runs `cargo check` with itself as `RUSTC_WRAPPER`.
+//! Cargo then calls it back for every crate in the dependency graph: dependencies are compiled
+//! with the real rustc, while the crates of the workspace are also handed to the sire driver.
+
+use std::env;
+use std::error::Error;
+use std::ffi::OsString;
+use std::path::{Path, PathBuf};
+use std::process::{self, Command, Stdio};
+
+const USAGE: &str = "\
+Usage: cargo sire <SIRE ARGS>... [-- <CARGO ARGS>...]
+
+Runs sire on every crate of the current workspace. The sire arguments are the same ones
+accepted by `sire-run` (run `sire-run --help` for more information), while the arguments
+after `--` are forwarded to `cargo check`.";
+
+/// Separator used to pack the sire arguments into a single environment variable.
+const SEPARATOR: char = '\u{1f}';
+
+fn main() {
+    let mut args = env::args_os().skip(1);
+
+    match env::var("SIRE_ARGS") {
+        // We are being called by cargo as a wrapper around rustc.
+        Ok(sire_args) => {
+            let rustc = args.next().expect("rustc path not found");
+            process::exit(wrap_rustc(rustc, args.collect(), &sire_args));
+        }
+        // We are being called as `cargo sire`.
+        Err(_) => {
+            // Cargo passes the name of the subcommand as the first argument.
+            if args.next().map(|arg| arg != "sire").unwrap_or(true) {
+                eprintln!("{}", USAGE);
+                process::exit(1);
+            }
+            match args.map(OsString::into_string).collect() {
+                Ok(args) => process::exit(run_cargo(args)),
+                Err(arg) => {
+                    eprintln!("error: the argument {:?} is not valid unicode", arg);
+                    process::exit(1);
+                }
+            }
+        }
+    }
+}
+
+fn run_cargo(args: Vec<String>) -> i32 {
+    if args.iter().any(|arg| arg == "-h" || arg == "--help") {
+        eprintln!("{}", USAGE);
+        return 0;
+    }
+
+    let mut args = args.splitn(2, |arg| arg == "--");
+    let sire_args = args.next().unwrap_or(&[]);
+    let cargo_args = args.next().unwrap_or(&[]);
+
+    let cargo = env::var_os("CARGO").unwrap_or_else(|| "cargo".into());
+    let workspace = match Workspace::read(&cargo) {
+        Ok(workspace) => workspace,
+        Err(err) => {
+            eprintln!("error: could not read the workspace metadata: {}", err);
+            return 1;
+        }
+    };
+    // Use a separate target directory so the artifacts of regular builds are not touched.
+    let target_dir = workspace.target_directory.join("sire");
+
+    // Only the workspace crates are cleaned, so they are always recompiled, and therefore
+    // analyzed, while the dependencies are compiled just once.
+    let status = Command::new(&cargo)
+        .arg("clean")
+        .arg("--target-dir")
+        .arg(&target_dir)
+        .args(workspace.packages.iter().flat_map(|package| vec!["-p", package]))
+        .status()
+        .expect("could not run cargo");
+    if !status.success() {
+        return status.code().unwrap_or(1);
+    }
+
+    let status = Command::new(&cargo)
+        .arg("check")
+        .arg("--target-dir")
+        .arg(&target_dir)
+        .args(cargo_args)
+        .env("RUSTC_WRAPPER", env::current_exe().unwrap())
+        .env("SIRE_ARGS", sire_args.join(&SEPARATOR.to_string()))
+        .status()
+        .expect("could not run cargo");
+
+    status.code().unwrap_or(1)
+}
+
+/// The parts of the output of `cargo metadata` used by this binary.
+struct Workspace {
+    /// The names of the packages that are members of the current workspace.
+    packages: Vec<String>,
+    target_directory: PathBuf,
+}
+
+impl Workspace {
+    fn read(cargo: &OsString) -> Result<Self, Box<dyn Error>> {
+        let output = Command::new(cargo)
+            .args(&["metadata", "--no-deps", "--format-version", "1"])
+            .stderr(Stdio::inherit())
+            .output()?;
+        if !output.status.success() {
+            return Err("`cargo metadata` failed".into());
+        }
+        let metadata: serde_json::Value = serde_json::from_slice(&output.stdout)?;
+        let packages = metadata["packages"]
+            .as_array()
+            .ok_or("the package list is missing")?
+            .iter()
+            .filter_map(|package| package["name"].as_str())
+            .map(Into::into)
+            .collect();
+        let target_directory =
+            metadata["target_directory"].as_str().ok_or("the target directory is missing")?.into();
+        Ok(Workspace { packages, target_directory })
+    }
+}
+
+fn wrap_rustc(rustc: OsString, rustc_args: Vec<OsString>, sire_args: &str) -> i32 {
+    // Always produce the artifacts with the real compiler, other crates might depend on them.
+    let status = Command::new(&rustc).args(&rustc_args).status().expect("could not run rustc");
+    if !status.success() {
+        return status.code().unwrap_or(1);
+    }
+
+    // Cargo sets this variable only for the crates that belong to the workspace, and build
+    // scripts are not interesting for sire.
+    let crate_name = rustc_args
+        .windows(2)
+        .find(|pair| pair[0] == "--crate-name")
+        .map(|pair| pair[1].to_string_lossy().into_owned())
+        .unwrap_or_default();
+    if env::var_os("CARGO_PRIMARY_PACKAGE").is_none() || crate_name == "build_script_build" {
+        return 0;
+    }
+
+    let sire_args = sire_args.split(SEPARATOR).filter(|arg| !arg.is_empty()).collect::<Vec<_>>();
+    let status = Command::new(driver_path())
+        .args(output_per_crate(&sire_args, &crate_name))
+        .arg("--")
+        .args(&rustc_args)
+        .status()
+        .expect("could not run the sire driver");
+
+    status.code().unwrap_or(1)
+}
+
+/// Every crate of the workspace is analyzed by its own run of the driver, so the file given to
+/// `--output` gets the name of the crate appended, like `report-my_crate.txt` for `report.txt`,
+/// instead of being overwritten by every crate.
+fn output_per_crate(sire_args: &[&str], crate_name: &str) -> Vec<String> {
+    let mut args = sire_args.iter().map(|arg| arg.to_string()).collect::<Vec<_>>();
+    for i in 1..args.len() {
+        if args[i - 1] == "--output" {
+            let path = Path::new(&args[i]);
+            let stem = path.file_stem().unwrap_or_default().to_string_lossy();
+            let mut name = format!("{}-{}", stem, crate_name);
+            if let Some(extension) = path.extension() {
+                name = format!("{}.{}", name, extension.to_string_lossy());
+            }
+            args[i] = path.with_file_name(name).to_string_lossy().into_owned();
+        }
+    }
+    args
+}
+
+/// The sire driver is installed next to this binary.
+fn driver_path() -> PathBuf {
+    let mut path = env::current_exe().unwrap();
+    path.set_file_name(format!("sire-run{}", env::consts::EXE_SUFFIX));
+    path
+}
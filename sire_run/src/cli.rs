@@ -0,0 +1,183 @@
+use std::fs::File;
+use std::io::{self, Write};
+
+use regex::Regex;
+
+use sire_smt::Solver;
+
+pub const USAGE: &str = "\
+Usage: sire_run [OPTIONS] <COMMAND> [--] <RUSTC ARGS>...
+
+Commands:
+    dump-sir            Print the SIR of every supported function
+    smt                 Print the SMT-LIB encoding of every supported function
+    check-equiv <A> <B> Check whether the functions with paths A and B are equivalent
+    verify              Check that the definition of every supported function is consistent
+    gen-tests           Write a test for every path of every supported function, with inputs
+                        found by the solver
+
+Options:
+    --filter <REGEX>    Only report and check the functions whose path matches REGEX
+    --output <FILE>     Write the results to FILE instead of the standard output
+    --format <FORMAT>   Format used by dump-sir, either text or json [default: text]
+    --cover <TARGETS>   Targets covered by gen-tests, either the paths through the MIR of every
+                        function or the arms of every switch in its SIR [default: paths]
+    --solver <SOLVER>   Solver used to discharge queries, either z3 or cvc4 [default: z3]
+    --prune             Ask the solver which branches can be taken while evaluating functions
+                        and leave the others out
+    --inline            Inline the calls to non-recursive functions before running the command
+    --depth <K>         Unroll recursive functions up to K nested calls instead of using
+                        quantifiers, so checks only hold for inputs within that depth
+    --max-paths <N>     Explore at most N paths of every function in gen-tests [default: 256]
+    -h, --help          Print this message
+
+Every argument that is not recognized by sire is forwarded to rustc, as well as every argument
+after `--`.";
+
+pub enum Command {
+    DumpSir,
+    Smt,
+    CheckEquiv(String, String),
+    Verify,
+    GenTests,
+}
+
+pub enum Format {
+    Text,
+    Json,
+}
+
+pub enum Coverage {
+    Paths,
+    Arms,
+}
+
+/// Why the command line arguments could not be turned into options.
+pub enum ParseError {
+    /// The usage was asked for with `-h` or `--help`.
+    Help,
+    Invalid(String),
+}
+
+impl From<String> for ParseError {
+    fn from(msg: String) -> Self {
+        ParseError::Invalid(msg)
+    }
+}
+
+pub struct Options {
+    pub command: Command,
+    pub filter: Option<Regex>,
+    pub output: Option<String>,
+    pub format: Format,
+    pub cover: Coverage,
+    pub solver: Solver,
+    pub prune: bool,
+    pub inline: bool,
+    pub depth: Option<usize>,
+    pub max_paths: usize,
+}
+
+impl Options {
+    /// Splits the command line arguments into sire's options and the arguments that must be
+    /// forwarded to rustc. The first argument is the binary name and it is kept for rustc.
+    pub fn parse(args: Vec<String>) -> Result<(Self, Vec<String>), ParseError> {
+        let mut args = args.into_iter();
+        let mut rustc_args = args.next().into_iter().collect::<Vec<_>>();
+
+        let mut command = None;
+        let mut filter = None;
+        let mut output = None;
+        let mut format = Format::Text;
+        let mut cover = Coverage::Paths;
+        let mut solver = Solver::default();
+        let mut prune = false;
+        let mut inline = false;
+        let mut depth = None;
+        let mut max_paths = 256;
+
+        while let Some(arg) = args.next() {
+            match arg.as_str() {
+                "--" => {
+                    rustc_args.extend(args);
+                    break;
+                }
+                "-h" | "--help" => return Err(ParseError::Help),
+                "--filter" => {
+                    let regex = value_of(&arg, args.next())?;
+                    filter = Some(Regex::new(&regex).map_err(|err| err.to_string())?);
+                }
+                "--output" => output = Some(value_of(&arg, args.next())?),
+                "--format" => {
+                    format = match value_of(&arg, args.next())?.as_str() {
+                        "text" => Format::Text,
+                        "json" => Format::Json,
+                        other => return Err(format!("unknown format `{}`", other).into()),
+                    }
+                }
+                "--cover" => {
+                    cover = match value_of(&arg, args.next())?.as_str() {
+                        "paths" => Coverage::Paths,
+                        "arms" => Coverage::Arms,
+                        other => return Err(format!("unknown coverage `{}`", other).into()),
+                    }
+                }
+                "--solver" => solver = value_of(&arg, args.next())?.parse()?,
+                "--prune" => prune = true,
+                "--inline" => inline = true,
+                "--depth" => {
+                    let value = value_of(&arg, args.next())?;
+                    depth = Some(value.parse().map_err(|_| format!("invalid depth `{}`", value))?);
+                }
+                "--max-paths" => {
+                    let value = value_of(&arg, args.next())?;
+                    max_paths = value
+                        .parse()
+                        .map_err(|_| format!("invalid number of paths `{}`", value))?;
+                }
+                "dump-sir" if command.is_none() => command = Some(Command::DumpSir),
+                "smt" if command.is_none() => command = Some(Command::Smt),
+                "verify" if command.is_none() => command = Some(Command::Verify),
+                "gen-tests" if command.is_none() => command = Some(Command::GenTests),
+                "check-equiv" if command.is_none() => {
+                    let a = value_of(&arg, args.next())?;
+                    let b = value_of(&arg, args.next())?;
+                    command = Some(Command::CheckEquiv(a, b));
+                }
+                _ => rustc_args.push(arg),
+            }
+        }
+
+        let command = command.ok_or_else(|| format!("no command was given\n\n{}", USAGE))?;
+
+        let options = Options {
+            command,
+            filter,
+            output,
+            format,
+            cover,
+            solver,
+            prune,
+            inline,
+            depth,
+            max_paths,
+        };
+
+        Ok((options, rustc_args))
+    }
+
+    pub fn is_selected(&self, path: &str) -> bool {
+        self.filter.as_ref().map(|regex| regex.is_match(path)).unwrap_or(true)
+    }
+
+    pub fn writer(&self) -> io::Result<Box<dyn Write>> {
+        Ok(match self.output {
+            Some(ref path) => Box::new(File::create(path)?),
+            None => Box::new(io::stdout()),
+        })
+    }
+}
+
+fn value_of(arg: &str, value: Option<String>) -> Result<String, String> {
+    value.ok_or_else(|| format!("`{}` expects a value", arg))
+}
@@ -5,27 +5,54 @@ extern crate rustc_driver;
 extern crate rustc_interface;
 extern crate syntax;
 
+use std::error::Error;
+use std::io::Write;
 
 use rustc::hir::{def_id::LOCAL_CRATE, ItemKind};
 use rustc_driver::{report_ices_to_stderr_if_any, run_compiler, Callbacks, Compilation};
 use rustc_interface::interface;
 
 use sire::eval::Evaluator;
+use sire::sir::{FuncId, Program, Value, VisitorMut};
+use sire_smt::bmc::{self, BoundedResult};
+use sire_smt::induction::{self, Outcome};
+use sire_smt::model::find_abort;
 use sire_smt::smtlib::ToSmtlib;
+use sire_smt::termination::{check_termination, Termination};
+use sire_smt::{check_consistency, check_equality, CheckResult};
+
+use crate::cli::{Command, Coverage, Format, Options, ParseError, USAGE};
+use crate::report::{Status, Summary};
+
+mod cli;
+mod report;
+mod testgen;
 
 fn find_sysroot() -> String {
+    if let Ok(sysroot) = std::env::var("SIRE_SYSROOT") {
+        return sysroot;
+    }
+
+    // Prefer the toolchain this binary was built with, as the driver is linked against it.
     let home = option_env!("RUSTUP_HOME").or(option_env!("MULTIRUST_HOME"));
     let toolchain = option_env!("RUSTUP_TOOLCHAIN").or(option_env!("MULTIRUST_TOOLCHAIN"));
 
     match (home, toolchain) {
         (Some(home), Some(toolchain)) => format!("{}/toolchains/{}", home, toolchain),
-        _ => option_env!("RUST_SYSROOT")
-            .expect("could not find sysroot")
-            .to_owned(),
+        _ => option_env!("RUST_SYSROOT").map(ToOwned::to_owned).unwrap_or_else(|| {
+            let output = std::process::Command::new("rustc")
+                .arg("--print")
+                .arg("sysroot")
+                .output()
+                .expect("could not find sysroot");
+            String::from_utf8(output.stdout).expect("invalid sysroot").trim().to_owned()
+        }),
     }
 }
 
-struct SireCompilerCalls;
+struct SireCompilerCalls {
+    options: Options,
+}
 
 impl Callbacks for SireCompilerCalls {
     fn after_parsing(&mut self, _compiler: &interface::Compiler) -> Compilation {
@@ -35,25 +62,39 @@ impl Callbacks for SireCompilerCalls {
     fn after_analysis(&mut self, compiler: &interface::Compiler) -> Compilation {
         compiler.session().abort_if_errors();
         compiler.global_ctxt().unwrap().peek_mut().enter(|tcx| {
-            let mut evaluator = Evaluator::from_tcx(tcx).unwrap();
-            let mut functions = Vec::new();
+            let mut evaluator = Evaluator::from_tcx(tcx);
+            if self.options.prune {
+                evaluator.set_solver(self.options.solver);
+            }
+            let mut summary = Summary::new(tcx);
 
-            let (main_id, _) = tcx.entry_fn(LOCAL_CRATE).expect("no main function found!");
+            // Libraries do not have an entry point.
+            let main_id = tcx.entry_fn(LOCAL_CRATE).map(|(def_id, _)| def_id);
 
             let hir = tcx.hir();
 
             for (&hir_id, item) in &hir.krate().items {
                 if let ItemKind::Fn(_, _, _, _) = item.node {
                     let def_id = hir.local_def_id(hir_id);
-                    if def_id != main_id {
-                        functions.push(evaluator.eval_mir(def_id).unwrap());
-                    }
+                    let selected = self.options.is_selected(&tcx.def_path_str(def_id));
+                    let status = if Some(def_id) == main_id {
+                        Status::Skipped("entry point".to_owned())
+                    } else {
+                        match evaluator.eval_mir(def_id) {
+                            Ok(func) => Status::Supported(func),
+                            Err(err) => Status::Unsupported(err),
+                        }
+                    };
+                    summary.insert(def_id, status, selected);
                 }
             }
 
-            for func in functions {
-                println!("{}", func);
-                println!("{}", func.to_smtlib());
+            summary.set_symbols(evaluator.symbols().clone());
+            summary.skip_dependents();
+            summary.print();
+
+            if let Err(err) = run_command(&self.options, &summary, &mut evaluator) {
+                tcx.sess.err(&format!("sire: {}", err));
             }
         });
 
@@ -62,8 +103,219 @@ impl Callbacks for SireCompilerCalls {
     }
 }
 
+fn run_command<'tcx>(
+    options: &Options,
+    summary: &Summary<'tcx>,
+    evaluator: &mut Evaluator<'tcx>,
+) -> Result<(), Box<dyn Error>> {
+    let mut out = options.writer()?;
+    let program = if options.inline { summary.program().inline() } else { summary.program() };
+    // The functions called by the selected ones are part of the program, but only the selected
+    // ones are printed or checked.
+    let selected = program
+        .topological_order()
+        .into_iter()
+        .filter(|func| summary.is_selected(func))
+        .collect::<Vec<_>>();
+
+    match options.command {
+        Command::DumpSir => match options.format {
+            Format::Text => {
+                for func in &selected {
+                    writeln!(out, "{}", func)?;
+                }
+            }
+            Format::Json => {
+                let funcs = selected
+                    .iter()
+                    .map(|func| {
+                        let mut body = func.body.clone();
+                        FunctionPaths(&program).visit_mut_expr(&mut body);
+                        serde_json::json!({
+                            "path": program.name(&func.id),
+                            "body": body,
+                            "ty": func.ty,
+                        })
+                    })
+                    .collect::<Vec<_>>();
+                serde_json::to_writer_pretty(&mut out, &funcs)?;
+                writeln!(out)?;
+            }
+        },
+        Command::Smt => {
+            let program =
+                program.restrict(&selected.iter().map(|func| &func.id).collect::<Vec<_>>());
+            match options.depth {
+                Some(depth) => writeln!(out, "{}", bmc::unroll(&program, depth))?,
+                None => writeln!(out, "{}", program.to_smtlib())?,
+            }
+        }
+        Command::CheckEquiv(ref a, ref b) => {
+            let a = summary.find(a).ok_or_else(|| format!("`{}` is not supported", a))?;
+            let b = summary.find(b).ok_or_else(|| format!("`{}` is not supported", b))?;
+            let verdict = match options.depth {
+                Some(depth) => {
+                    let result =
+                        bmc::check_equality(&program, &a.id, &b.id, depth, options.solver)?;
+                    bounded_verdict(result, depth, "equivalent", "not equivalent")
+                }
+                None => match induction::induction_function(&program, &a.id, &b.id) {
+                    Some(id) if prove_by_induction(&mut out, &program, &a.id, &b.id, options)? => {
+                        format!("equivalent (by induction on `{}`)", program.name(id))
+                    }
+                    _ => {
+                        let result = check_equality(&program, &a.id, &b.id, options.solver)?;
+                        verdict(result, "equivalent", "not equivalent")
+                    }
+                },
+            };
+            writeln!(out, "{}", verdict)?;
+        }
+        Command::Verify => {
+            for func in &selected {
+                let verdict = match options.depth {
+                    Some(depth) => {
+                        let result =
+                            bmc::check_assertions(&program, &func.id, depth, options.solver)?;
+                        bounded_verdict(result, depth, "no assertion fails", "an assertion fails")
+                    }
+                    None => {
+                        let result = check_consistency(&program, &func.id, options.solver)?;
+                        let mut verdict = verdict(result, "consistent", "inconsistent");
+                        if let Some(inputs) = find_abort(&program, &func.id, options.solver)? {
+                            let inputs = inputs.iter().map(testgen::literal).collect::<Vec<_>>();
+                            verdict = format!("{}, aborts for ({})", verdict, inputs.join(", "));
+                        }
+                        match check_termination(&program, &func.id, options.solver)? {
+                            Termination::NonRecursive => verdict,
+                            Termination::Decreasing(index) => {
+                                format!("{}, terminates (`_{}` decreases)", verdict, index)
+                            }
+                            Termination::Unproved => {
+                                format!("{}, termination could not be proved", verdict)
+                            }
+                        }
+                    }
+                };
+                writeln!(out, "{}: {}", summary.path(func), verdict)?;
+            }
+        }
+        Command::GenTests => {
+            // The tests of every function go in the module defining it, so they can call private
+            // functions.
+            let mut modules: Vec<(String, Vec<String>)> = Vec::new();
+            for func in &selected {
+                let path = summary.path(func);
+                let module = match summary.module(func) {
+                    Some(module) => module,
+                    None => {
+                        writeln!(out, "// `{}`: nested functions are not tested", path)?;
+                        continue;
+                    }
+                };
+                let mut tests = Vec::new();
+                let targets = match options.cover {
+                    Coverage::Paths => {
+                        let def_id = summary.def_id(func);
+                        let exploration = evaluator.explore_paths(def_id, options.max_paths)?;
+                        for conditions in &exploration.unexplored {
+                            tests.push(testgen::unexplored(&path, conditions, options.max_paths));
+                        }
+                        testgen::paths(&exploration.paths)
+                    }
+                    Coverage::Arms => testgen::arms(func),
+                };
+                tests.extend(testgen::tests(&program, func, &path, &targets, options.solver)?);
+                match modules.iter_mut().find(|(other, _)| *other == module) {
+                    Some((_, module_tests)) => module_tests.extend(tests),
+                    None => modules.push((module, tests)),
+                }
+            }
+            let code = modules
+                .iter()
+                .map(|(module, tests)| testgen::test_module(module, tests))
+                .collect::<Vec<_>>();
+            writeln!(out, "{}", code.join("\n\n"))?;
+        }
+    }
+
+    Ok(())
+}
+
+/// Discharges the induction obligations one by one, reporting the first one that does not hold.
+fn prove_by_induction(
+    out: &mut dyn Write,
+    program: &Program,
+    a: &FuncId,
+    b: &FuncId,
+    options: &Options,
+) -> Result<bool, Box<dyn Error>> {
+    // The induction follows the recursion, so it is unsound if the recursion does not end.
+    let id = induction::induction_function(program, a, b).unwrap();
+    if check_termination(program, id, options.solver)? == Termination::Unproved {
+        let name = program.name(id);
+        writeln!(out, "induction skipped: the termination of `{}` could not be proved", name)?;
+        return Ok(false);
+    }
+
+    for obligation in induction::obligations(program, a, b)? {
+        let outcome = obligation.discharge(options.solver)?;
+        if outcome != Outcome::Holds {
+            let reason = match outcome {
+                Outcome::Fails => "does not hold".to_owned(),
+                Outcome::Undecided => "could not be decided".to_owned(),
+                Outcome::Unknown(output) => format!("gave unexpected solver output: {}", output),
+                Outcome::Holds => unreachable!(),
+            };
+            writeln!(out, "induction failed: the {} {}", obligation, reason)?;
+            return Ok(false);
+        }
+    }
+    Ok(true)
+}
+
+fn verdict(result: CheckResult, sat: &str, unsat: &str) -> String {
+    match result {
+        CheckResult::Sat => sat.to_owned(),
+        CheckResult::Unsat => unsat.to_owned(),
+        CheckResult::Undecided => "unknown".to_owned(),
+        CheckResult::Unknown(output) => format!("unexpected solver output: {}", output),
+    }
+}
+
+fn bounded_verdict(result: BoundedResult, depth: usize, holds: &str, fails: &str) -> String {
+    match result {
+        BoundedResult::Verified => format!("{} up to depth {}", holds, depth),
+        BoundedResult::Falsified => format!("{} within depth {}", fails, depth),
+        BoundedResult::Undecided => "unknown".to_owned(),
+        BoundedResult::Unknown(output) => format!("unexpected solver output: {}", output),
+    }
+}
+
+/// Replaces the identifiers of the functions called by an expression with their paths, which
+/// unlike the identifiers do not depend on the other functions of the program.
+struct FunctionPaths<'a>(&'a Program);
+
+impl<'a> VisitorMut for FunctionPaths<'a> {
+    fn visit_mut_value(&mut self, value: &mut Value) {
+        if let Value::Function(id, _) = value {
+            *id = FuncId(self.0.name(id));
+        }
+    }
+}
+
 fn main() {
-    let mut rustc_args = std::env::args().collect::<Vec<_>>();
+    let (options, mut rustc_args) = match Options::parse(std::env::args().collect()) {
+        Ok(parsed) => parsed,
+        Err(ParseError::Help) => {
+            println!("{}", USAGE);
+            return;
+        }
+        Err(ParseError::Invalid(msg)) => {
+            eprintln!("{}", msg);
+            std::process::exit(1);
+        }
+    };
     let sysroot_flag = String::from("--sysroot");
 
     if !rustc_args.contains(&sysroot_flag) {
@@ -72,7 +324,7 @@ fn main() {
     }
 
     let result = report_ices_to_stderr_if_any(move || {
-        run_compiler(&rustc_args, &mut SireCompilerCalls, None, None)
+        run_compiler(&rustc_args, &mut SireCompilerCalls { options }, None, None)
     })
     .and_then(|result| result);
 
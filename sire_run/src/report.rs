@@ -0,0 +1,162 @@
+use rustc::hir::def::DefKind;
+use rustc::hir::def_id::DefId;
+use rustc::ty::TyCtxt;
+
+use sire::sir::{FuncDef, FuncId, Program, SymbolTable};
+use sire::Error;
+
+/// The outcome of evaluating a single function.
+pub enum Status {
+    Supported(FuncDef),
+    Unsupported(Error),
+    Skipped(String),
+}
+
+struct Entry {
+    def_id: DefId,
+    status: Status,
+    /// Whether the function matches `--filter`. The other functions are evaluated because the
+    /// selected ones might call them, but they are neither reported nor checked.
+    selected: bool,
+}
+
+pub struct Summary<'tcx> {
+    tcx: TyCtxt<'tcx>,
+    entries: Vec<Entry>,
+    symbols: SymbolTable,
+}
+
+impl<'tcx> Summary<'tcx> {
+    pub fn new(tcx: TyCtxt<'tcx>) -> Self {
+        Summary { tcx, entries: Vec::new(), symbols: SymbolTable::default() }
+    }
+
+    /// Sets the table used to name the functions referenced by the evaluated ones.
+    pub fn set_symbols(&mut self, symbols: SymbolTable) {
+        self.symbols = symbols;
+    }
+
+    pub fn insert(&mut self, def_id: DefId, status: Status, selected: bool) {
+        if let Status::Unsupported(ref err) = status {
+            if selected {
+                report_error(self.tcx, def_id, err);
+            }
+        }
+        self.entries.push(Entry { def_id, status, selected });
+    }
+
+    /// Skips every supported function that calls a function which is not supported itself, as
+    /// its definition would reference an undeclared symbol.
+    pub fn skip_dependents(&mut self) {
+        loop {
+            let supported = self.supported().map(|func| &func.id).collect::<Vec<_>>();
+            let skipped = self
+                .entries
+                .iter()
+                .enumerate()
+                .filter_map(|(i, entry)| match &entry.status {
+                    Status::Supported(func) => func
+                        .body
+                        .find_callees()
+                        .into_iter()
+                        .find(|id| *id != func.id && !supported.contains(&id))
+                        .map(|callee| (i, format!("calls `{}`", self.path_of(&callee)))),
+                    _ => None,
+                })
+                .collect::<Vec<_>>();
+
+            if skipped.is_empty() {
+                break;
+            }
+
+            for (i, reason) in skipped {
+                self.entries[i].status = Status::Skipped(reason);
+            }
+        }
+    }
+
+    pub fn supported(&self) -> impl Iterator<Item = &FuncDef> {
+        self.entries.iter().filter_map(|entry| match &entry.status {
+            Status::Supported(func) => Some(func),
+            _ => None,
+        })
+    }
+
+    /// Returns the `DefId` of a supported function.
+    pub fn def_id(&self, func: &FuncDef) -> DefId {
+        self.entry(func).def_id
+    }
+
+    /// Whether a supported function matches `--filter`.
+    pub fn is_selected(&self, func: &FuncDef) -> bool {
+        self.entry(func).selected
+    }
+
+    fn entry(&self, func: &FuncDef) -> &Entry {
+        self.entries
+            .iter()
+            .find(|entry| match &entry.status {
+                Status::Supported(supported) => supported.id == func.id,
+                _ => false,
+            })
+            .expect("the function is not supported")
+    }
+
+    /// Returns the path of the module defining a supported function, which is empty for the crate
+    /// root, or `None` if the function is defined inside another function.
+    pub fn module(&self, func: &FuncDef) -> Option<String> {
+        let parent = self.tcx.parent(self.def_id(func))?;
+        let is_root = self.tcx.parent(parent).is_none();
+        if is_root || self.tcx.def_kind(parent) == Some(DefKind::Mod) {
+            Some(self.tcx.def_path_str(parent))
+        } else {
+            None
+        }
+    }
+
+    pub fn find(&self, path: &str) -> Option<&FuncDef> {
+        self.supported().find(|func| self.path(func) == path)
+    }
+
+    pub fn path(&self, func: &FuncDef) -> String {
+        self.path_of(&func.id)
+    }
+
+    fn path_of(&self, id: &FuncId) -> String {
+        self.symbols.name(id)
+    }
+
+    /// Returns the program made of every supported function.
+    pub fn program(&self) -> Program {
+        Program::new(self.supported().cloned().collect(), self.symbols.clone())
+    }
+
+    /// Prints a table with the status of every selected function to `stderr`.
+    pub fn print(&self) {
+        let entries = self.entries.iter().filter(|entry| entry.selected).collect::<Vec<_>>();
+        let names =
+            entries.iter().map(|entry| self.tcx.def_path_str(entry.def_id)).collect::<Vec<_>>();
+        let width = names.iter().map(|name| name.len()).max().unwrap_or(0);
+
+        eprintln!("{:<width$}  status", "function", width = width);
+        for (name, entry) in names.iter().zip(entries) {
+            let status = match &entry.status {
+                Status::Supported(_) => "supported".to_owned(),
+                Status::Unsupported(err) => format!("unsupported: {}", err.kind),
+                Status::Skipped(reason) => format!("skipped: {}", reason),
+            };
+            eprintln!("{:<width$}  {}", name, status, width = width);
+        }
+    }
+}
+
+/// Reports an evaluation error through the compiler session, pointing at the offending code if
+/// the error has a span and at the function being evaluated otherwise.
+fn report_error(tcx: TyCtxt<'_>, def_id: DefId, err: &Error) {
+    let mut diag = match err.span {
+        Some(span) => tcx.sess.struct_span_warn(span, &format!("sire: {} here", err.kind)),
+        None => tcx.sess.struct_span_warn(tcx.def_span(def_id), &format!("sire: {}", err.kind)),
+    };
+    diag.note(&format!("while evaluating `{}`", tcx.def_path_str(def_id)));
+    diag.emit();
+}
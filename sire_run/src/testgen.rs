@@ -0,0 +1,135 @@
+use std::error::Error;
+
+use sire::sir::{Expr, FuncDef, Path, Program, Ty, Value};
+use sire_smt::model::find_inputs;
+use sire_smt::Solver;
+
+/// Writes a `#[test]` function for each target of the function, given by a label and the
+/// conditions that reach it, like the ones of a path or of the arm of a switch. The inputs come
+/// from the solver and the expected output from evaluating the SIR of the function with them.
+/// Targets without inputs are listed in comments and targets reached by the inputs of a previous
+/// one are skipped. The tests call the function by its name, so they must be placed in the module
+/// defining it.
+pub fn tests(
+    program: &Program,
+    func: &FuncDef,
+    path: &str,
+    targets: &[(String, Vec<Expr>)],
+    solver: Solver,
+) -> Result<Vec<String>, Box<dyn Error>> {
+    let supported = match &func.ty {
+        Ty::Func(args_ty, params) => params.is_empty() && args_ty.iter().all(is_scalar),
+        _ => false,
+    };
+    if !supported {
+        return Ok(vec![format!(
+            "// `{}`: only functions over integers and booleans are tested",
+            path
+        )]);
+    }
+
+    let name = path.rsplit("::").next().unwrap();
+    let mut tests = Vec::new();
+    let mut covered = Vec::new();
+    for (label, conditions) in targets {
+        let inputs = match find_inputs(program, &func.id, conditions, solver)? {
+            Some(inputs) => inputs,
+            None => {
+                tests.push(format!("// `{}`: no inputs were found for the {}", path, label));
+                continue;
+            }
+        };
+        if covered.contains(&inputs) {
+            continue;
+        }
+        let output = match program.call(&func.id, &inputs) {
+            Some(output) => output,
+            None => {
+                tests.push(format!("// `{}`: the output for the {} is unknown", path, label));
+                continue;
+            }
+        };
+        let args = inputs.iter().map(literal).collect::<Vec<_>>().join(", ");
+        tests.push(format!(
+            "#[test]\nfn {}_{}() {{\n    assert_eq!({}({}), {});\n}}",
+            test_name(name),
+            test_name(label),
+            name,
+            args,
+            literal(&output)
+        ));
+        covered.push(inputs);
+    }
+    Ok(tests)
+}
+
+/// Targets covering every path of the function.
+pub fn paths(paths: &[Path]) -> Vec<(String, Vec<Expr>)> {
+    paths
+        .iter()
+        .enumerate()
+        .map(|(i, path)| (format!("path {}", i), path.conditions.clone()))
+        .collect()
+}
+
+/// Lists a branch that was not explored because the function has more than `max_paths` paths.
+pub fn unexplored(path: &str, conditions: &[Expr], max_paths: usize) -> String {
+    let conditions = conditions.iter().map(ToString::to_string).collect::<Vec<_>>().join(" and ");
+    format!(
+        "// `{}`: the paths where {} were not explored, as there are more than {}",
+        path, conditions, max_paths
+    )
+}
+
+/// Targets covering every arm of the switches of the function, or its whole body if it has none.
+pub fn arms(func: &FuncDef) -> Vec<(String, Vec<Expr>)> {
+    let arms = func.body.arms();
+    if arms.is_empty() {
+        return vec![("body".to_owned(), Vec::new())];
+    }
+    arms.into_iter().enumerate().map(|(i, conditions)| (format!("arm {}", i), conditions)).collect()
+}
+
+/// Wraps the tests of the functions defined in `module` in a submodule that is only compiled for
+/// testing and has to be added to `module` itself.
+pub fn test_module(module: &str, tests: &[String]) -> String {
+    let body = tests
+        .join("\n\n")
+        .lines()
+        .map(|line| if line.is_empty() { String::new() } else { format!("    {}", line) })
+        .collect::<Vec<_>>()
+        .join("\n");
+    let module =
+        if module.is_empty() { "the crate root".to_owned() } else { format!("`{}`", module) };
+    format!(
+        "// Tests for {}\n#[cfg(test)]\nmod sire_tests {{\n    use super::*;\n\n{}\n}}",
+        module, body
+    )
+}
+
+fn is_scalar(ty: &Ty) -> bool {
+    match ty {
+        Ty::Bool | Ty::Int(_) | Ty::Uint(_) => true,
+        _ => false,
+    }
+}
+
+fn test_name(path: &str) -> String {
+    path.replace("::", "_")
+        .chars()
+        .map(|c| if c.is_ascii_alphanumeric() || c == '_' { c } else { '_' })
+        .collect()
+}
+
+/// Writes a constant as a Rust literal.
+pub fn literal(value: &Value) -> String {
+    match value {
+        Value::Const(bits, Ty::Bool) => (*bits != 0).to_string(),
+        Value::Const(bits, Ty::Int(size)) => {
+            let shift = 128 - size;
+            ((*bits << shift) as i128 >> shift).to_string()
+        }
+        Value::Const(bits, _) => bits.to_string(),
+        _ => unreachable!(),
+    }
+}
@@ -0,0 +1,156 @@
+use super::*;
+
+impl Expr {
+    pub fn find_datatype_instances(&self) -> Vec<Ty> {
+        Instanced::find_types(self)
+    }
+}
+
+#[derive(Default)]
+struct Instanced {
+    inner: Vec<Ty>,
+}
+
+impl Instanced {
+    fn find_types(expr: &Expr) -> Vec<Ty> {
+        let mut this = Self::default();
+        this.visit_expr(expr);
+        this.inner
+    }
+}
+
+impl Visitor for Instanced {
+    #[allow(clippy::single_match, clippy::collapsible_match)]
+    fn visit_expr(&mut self, expr: &Expr) {
+        self.super_expr(expr);
+        let ty = expr.ty();
+
+        match ty {
+            Ty::Tuple(_) => {
+                if !self.inner.contains(&ty) {
+                    self.inner.push(ty);
+                }
+            }
+            _ => (),
+        }
+    }
+}
+
+impl Expr {
+    /// Returns the functions called in the expression, in the order of their first call. Shared
+    /// subexpressions are searched once.
+    pub fn find_callees(&self) -> Vec<FuncId> {
+        let mut arena = ExprArena::default();
+        arena.insert(self);
+
+        let mut callees = Vec::new();
+        for node in arena.nodes() {
+            if let Node::Apply(func, _) = node {
+                if let Node::Value(Value::Function(id, _)) = arena.node(*func) {
+                    if !callees.contains(id) {
+                        callees.push(id.clone());
+                    }
+                }
+            }
+        }
+        callees
+    }
+}
+
+/// A path through the outermost `Switch`es of an expression.
+#[derive(Clone, Debug, PartialEq, Eq)]
+pub struct Path {
+    /// Boolean expressions that hold when the evaluation takes this path.
+    pub conditions: Vec<Expr>,
+    pub result: Expr,
+}
+
+impl Expr {
+    /// Splits the expression into its paths, so that the value of the expression is the `result`
+    /// of the path whose `conditions` hold.
+    pub fn paths(&self) -> Vec<Path> {
+        if let Expr::Switch(discr, values, results) = self {
+            let mut paths = Vec::new();
+            for (i, result) in results.iter().enumerate() {
+                let conditions = arm_conditions(discr, values, i);
+                paths.extend(result.paths().into_iter().map(|path| Path {
+                    conditions: conditions.iter().cloned().chain(path.conditions).collect(),
+                    result: path.result,
+                }));
+            }
+            paths
+        } else {
+            vec![Path { conditions: Vec::new(), result: self.clone() }]
+        }
+    }
+
+    /// Returns the conditions under which each arm of every `Switch` in the expression is taken,
+    /// every arm before the arms of the switches nested in it.
+    pub fn arms(&self) -> Vec<Vec<Expr>> {
+        let mut arms = Vec::new();
+        visit_guarded(self, &[], &mut |expr, context| {
+            if let Expr::Switch(discr, values, results) = expr {
+                arms.extend((0..results.len()).map(|i| {
+                    context.iter().cloned().chain(arm_conditions(discr, values, i)).collect()
+                }));
+            }
+        });
+        arms
+    }
+}
+
+/// The conditions under which the `i`-th result of a switch is taken.
+fn arm_conditions(discr: &Rc<Expr>, values: &[Rc<Expr>], i: usize) -> Vec<Expr> {
+    match values.get(i) {
+        Some(value) => vec![compare(BinOp::Eq, discr, value)],
+        // The last result is taken when no value matches.
+        None => values.iter().map(|value| compare(BinOp::Ne, discr, value)).collect(),
+    }
+}
+
+/// Calls `visit` with every subexpression that is evaluated, before its own subexpressions, and
+/// the conditions under which it is.
+fn visit_guarded(expr: &Expr, context: &[Expr], visit: &mut dyn FnMut(&Expr, &[Expr])) {
+    visit(expr, context);
+    match expr {
+        Expr::Switch(discr, values, results) => {
+            visit_guarded(discr, context, visit);
+            for (i, result) in results.iter().enumerate() {
+                let conditions = context
+                    .iter()
+                    .cloned()
+                    .chain(arm_conditions(discr, values, i))
+                    .collect::<Vec<_>>();
+                visit_guarded(result, &conditions, visit);
+            }
+        }
+        Expr::Apply(func, args) => {
+            visit_guarded(func, context, visit);
+            for arg in args {
+                visit_guarded(arg, context, visit);
+            }
+        }
+        Expr::BinaryOp(_, e1, e2) => {
+            visit_guarded(e1, context, visit);
+            visit_guarded(e2, context, visit);
+        }
+        Expr::Tuple(fields) => {
+            for field in fields {
+                visit_guarded(field, context, visit);
+            }
+        }
+        Expr::Projection(tuple, _) => visit_guarded(tuple, context, visit),
+        // The result is only evaluated if the assertion holds.
+        Expr::Assert(condition, result) => {
+            visit_guarded(condition, context, visit);
+            let mut conditions = context.to_vec();
+            conditions.push((**condition).clone());
+            visit_guarded(result, &conditions, visit);
+        }
+        Expr::Value(_) | Expr::Abort(_) | Expr::Uninitialized => (),
+    }
+}
+
+fn compare(bin_op: BinOp, e1: &Rc<Expr>, e2: &Rc<Expr>) -> Expr {
+    Expr::BinaryOp(bin_op, e1.clone(), e2.clone())
+}
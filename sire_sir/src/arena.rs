@@ -0,0 +1,194 @@
+use std::collections::HashMap;
+
+use super::*;
+
+/// The index of an expression in an `ExprArena`.
+#[derive(Clone, Copy, Debug, PartialEq, Eq, PartialOrd, Ord, Hash)]
+pub struct ExprId(usize);
+
+impl ExprId {
+    pub fn index(self) -> usize {
+        self.0
+    }
+}
+
+/// A node of an expression whose subexpressions are stored in an `ExprArena`.
+#[derive(Clone, Debug, PartialEq, Eq, Hash)]
+pub enum Node {
+    Value(Value),
+    Apply(ExprId, Vec<ExprId>),
+    BinaryOp(BinOp, ExprId, ExprId),
+    Switch(ExprId, Vec<ExprId>, Vec<ExprId>),
+    Tuple(Vec<ExprId>),
+    Projection(ExprId, usize),
+    Assert(ExprId, ExprId),
+    Abort(Ty),
+    Uninitialized,
+}
+
+impl Node {
+    pub fn children(&self) -> Vec<ExprId> {
+        match self {
+            Node::Value(_) | Node::Abort(_) | Node::Uninitialized => Vec::new(),
+            Node::Apply(func, args) => {
+                Some(*func).into_iter().chain(args.iter().copied()).collect()
+            }
+            Node::BinaryOp(_, e1, e2) | Node::Assert(e1, e2) => vec![*e1, *e2],
+            Node::Switch(discr, values, results) => {
+                Some(*discr).into_iter().chain(values.iter().chain(results).copied()).collect()
+            }
+            Node::Tuple(fields) => fields.clone(),
+            Node::Projection(tuple, _) => vec![*tuple],
+        }
+    }
+}
+
+/// Hash-consed storage for expressions: structurally equal expressions are stored once and get
+/// the same `ExprId`, so an expression that appears in many places is shared instead of copied.
+#[derive(Clone, Debug, Default)]
+pub struct ExprArena {
+    nodes: Vec<Node>,
+    ids: HashMap<Node, ExprId>,
+}
+
+impl ExprArena {
+    /// Returns the id of the node, adding it to the arena if it is not there yet.
+    pub fn intern(&mut self, node: Node) -> ExprId {
+        if let Some(&id) = self.ids.get(&node) {
+            return id;
+        }
+        let id = ExprId(self.nodes.len());
+        self.nodes.push(node.clone());
+        self.ids.insert(node, id);
+        id
+    }
+
+    /// Adds every subexpression of the expression to the arena. Subexpressions shared through an
+    /// `Rc` are added once, so this takes time linear in the number of distinct subexpressions.
+    pub fn insert(&mut self, expr: &Expr) -> ExprId {
+        self.insert_shared(expr, &mut HashMap::new())
+    }
+
+    fn insert_shared(
+        &mut self,
+        expr: &Expr,
+        inserted: &mut HashMap<*const Expr, ExprId>,
+    ) -> ExprId {
+        if let Some(&id) = inserted.get(&(expr as *const Expr)) {
+            return id;
+        }
+        let mut insert = |expr: &Expr| self.insert_shared(expr, inserted);
+        let node = match expr {
+            Expr::Value(value) => Node::Value(value.clone()),
+            Expr::Apply(func, args) => {
+                Node::Apply(insert(func), args.iter().map(|arg| insert(arg)).collect())
+            }
+            Expr::BinaryOp(bin_op, e1, e2) => Node::BinaryOp(*bin_op, insert(e1), insert(e2)),
+            Expr::Switch(discr, values, results) => Node::Switch(
+                insert(discr),
+                values.iter().map(|value| insert(value)).collect(),
+                results.iter().map(|result| insert(result)).collect(),
+            ),
+            Expr::Tuple(fields) => Node::Tuple(fields.iter().map(|field| insert(field)).collect()),
+            Expr::Projection(tuple, index) => Node::Projection(insert(tuple), *index),
+            Expr::Assert(condition, result) => Node::Assert(insert(condition), insert(result)),
+            Expr::Abort(ty) => Node::Abort(ty.clone()),
+            Expr::Uninitialized => Node::Uninitialized,
+        };
+        let id = self.intern(node);
+        inserted.insert(expr, id);
+        id
+    }
+
+    pub fn node(&self, id: ExprId) -> &Node {
+        &self.nodes[id.0]
+    }
+
+    /// Returns the nodes of the arena, every one of them after its subexpressions.
+    pub fn nodes(&self) -> &[Node] {
+        &self.nodes
+    }
+
+    pub fn len(&self) -> usize {
+        self.nodes.len()
+    }
+
+    pub fn is_empty(&self) -> bool {
+        self.nodes.is_empty()
+    }
+
+    /// Builds the expression with the given root. Subexpressions used more than once are shared
+    /// through an `Rc`, so the expression takes as much memory as the arena.
+    pub fn expr(&self, id: ExprId) -> Expr {
+        Expr::clone(&self.shared_expr(id, &mut HashMap::new()))
+    }
+
+    fn shared_expr(&self, id: ExprId, built: &mut HashMap<ExprId, Rc<Expr>>) -> Rc<Expr> {
+        if let Some(expr) = built.get(&id) {
+            return expr.clone();
+        }
+        let mut expr = |id| self.shared_expr(id, built);
+        let expr = Rc::new(match self.node(id) {
+            Node::Value(value) => Expr::Value(value.clone()),
+            Node::Apply(func, args) => {
+                Expr::Apply(expr(*func), args.iter().map(|&arg| expr(arg)).collect())
+            }
+            Node::BinaryOp(bin_op, e1, e2) => Expr::BinaryOp(*bin_op, expr(*e1), expr(*e2)),
+            Node::Switch(discr, values, results) => Expr::Switch(
+                expr(*discr),
+                values.iter().map(|&value| expr(value)).collect(),
+                results.iter().map(|&result| expr(result)).collect(),
+            ),
+            Node::Tuple(fields) => Expr::Tuple(fields.iter().map(|&field| expr(field)).collect()),
+            Node::Projection(tuple, index) => Expr::Projection(expr(*tuple), *index),
+            Node::Assert(condition, result) => Expr::Assert(expr(*condition), expr(*result)),
+            Node::Abort(ty) => Expr::Abort(ty.clone()),
+            Node::Uninitialized => Expr::Uninitialized,
+        });
+        built.insert(id, expr.clone());
+        expr
+    }
+
+    /// Returns the subexpressions of the expression with the given root that are used more than
+    /// once, every one of them after the shared subexpressions it contains.
+    pub fn shared(&self, root: ExprId) -> Vec<ExprId> {
+        let mut uses = HashMap::new();
+        let mut order = Vec::new();
+        self.count_uses(root, &mut uses, &mut order);
+        order.into_iter().filter(|id| uses[id] > 1).collect()
+    }
+
+    fn count_uses(&self, id: ExprId, uses: &mut HashMap<ExprId, usize>, order: &mut Vec<ExprId>) {
+        let count = uses.entry(id).or_insert(0);
+        *count += 1;
+        if *count == 1 {
+            for child in self.node(id).children() {
+                self.count_uses(child, uses, order);
+            }
+            order.push(id);
+        }
+    }
+
+    pub fn ty(&self, id: ExprId) -> Ty {
+        match self.node(id) {
+            Node::Value(value) => value.ty(),
+            Node::Apply(func, _) => match self.ty(*func) {
+                Ty::Func(args_ty, _) => args_ty[0].clone(),
+                _ => unreachable!(),
+            },
+            Node::BinaryOp(bin_op, e1, _) => match bin_op {
+                BinOp::Eq | BinOp::Lt | BinOp::Le | BinOp::Ne | BinOp::Ge | BinOp::Gt => Ty::Bool,
+                _ => self.ty(*e1),
+            },
+            Node::Switch(_, _, results) => self.ty(results[0]),
+            Node::Tuple(fields) => Ty::Tuple(fields.iter().map(|&field| self.ty(field)).collect()),
+            Node::Projection(tuple, index) => match self.ty(*tuple) {
+                Ty::Tuple(fields_ty) => fields_ty[*index].clone(),
+                _ => unreachable!(),
+            },
+            Node::Assert(_, result) => self.ty(*result),
+            Node::Abort(ty) => ty.clone(),
+            Node::Uninitialized => unreachable!(),
+        }
+    }
+}
@@ -9,7 +9,14 @@ impl fmt::Display for FuncDef {
             _ => unreachable!(),
         };
 
-        write!(f, "(defun {:?}[{}] {} {})", self.def_id, params, self.ty, self.body)
+        write!(f, "(defun {}[{}] ({}) {})", self.id, params, self.ty, self.body)
+    }
+}
+
+impl fmt::Display for FuncId {
+    fn fmt(&self, f: &mut fmt::Formatter<'_>) -> fmt::Result {
+        let FuncId(name) = self;
+        write!(f, "{}", name)
     }
 }
 
@@ -48,23 +55,7 @@ impl fmt::Display for Expr {
                 func,
                 args.iter().map(|x| x.to_string()).collect::<Vec<_>>().join(" ")
             ),
-            Expr::BinaryOp(op, e1, e2) => {
-                let op_string = match op {
-                    BinOp::Add => "+",
-                    BinOp::Sub => "-",
-                    BinOp::Mul => "*",
-                    BinOp::Div => "/",
-                    BinOp::Rem => "%",
-                    BinOp::Eq => "=",
-                    BinOp::Lt => "<",
-                    BinOp::Le => "<=",
-                    BinOp::Ne => "!=",
-                    BinOp::Ge => ">=",
-                    BinOp::Gt => ">",
-                    _ => unreachable!(),
-                };
-                write!(f, "({} {} {})", op_string, e1, e2)
-            }
+            Expr::BinaryOp(op, e1, e2) => write!(f, "({} {} {})", op, e1, e2),
             Expr::Switch(value, branches, targets) => write!(
                 f,
                 "(switch {} {} (else -> {}))",
@@ -84,6 +75,7 @@ impl fmt::Display for Expr {
             ),
             Expr::Projection(e1, i) => write!(f, "(proj {} {})", e1, i),
             Expr::Assert(e1, e2) => write!(f, "(assert {} {})", e1, e2),
+            Expr::Abort(ty) => write!(f, "(abort {})", ty),
             Expr::Uninitialized => write!(f, "uninitialized"),
         }
     }
@@ -94,8 +86,32 @@ impl fmt::Display for Value {
         match self {
             Value::Arg(n, _) => write!(f, "_{}", n),
             Value::Const(value, ty) => write!(f, "(const {} {})", ty, value),
-            Value::Function(def_id, _) => write!(f, "{:?}", def_id),
+            Value::Function(id, _) => write!(f, "{}", id),
             Value::ConstParam(Param(index, _)) => write!(f, "p{}", index),
         }
     }
 }
+
+impl fmt::Display for BinOp {
+    fn fmt(&self, f: &mut fmt::Formatter<'_>) -> fmt::Result {
+        let op_string = match self {
+            BinOp::Add => "+",
+            BinOp::Sub => "-",
+            BinOp::Mul => "*",
+            BinOp::Div => "/",
+            BinOp::Rem => "%",
+            BinOp::BitXor => "^",
+            BinOp::BitAnd => "&",
+            BinOp::BitOr => "|",
+            BinOp::Shl => "<<",
+            BinOp::Shr => ">>",
+            BinOp::Eq => "=",
+            BinOp::Lt => "<",
+            BinOp::Le => "<=",
+            BinOp::Ne => "!=",
+            BinOp::Ge => ">=",
+            BinOp::Gt => ">",
+        };
+        write!(f, "{}", op_string)
+    }
+}
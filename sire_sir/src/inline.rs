@@ -0,0 +1,80 @@
+use super::*;
+
+impl Program {
+    /// Returns a copy of the program where every call to a function of the program that is not
+    /// recursive has been replaced by the body of the callee.
+    pub fn inline(&self) -> Program {
+        let funcs = self
+            .funcs()
+            .iter()
+            .map(|func| {
+                let mut func = func.clone();
+                func.body.inline(self);
+                func
+            })
+            .collect();
+        Program::new(funcs, self.symbols().clone())
+    }
+}
+
+impl Expr {
+    /// Replaces every call to a non-recursive function of `program` by the body of the callee,
+    /// with its arguments substituted by the ones of the call.
+    pub fn inline(&mut self, program: &Program) {
+        Inliner { program }.visit_mut_expr(self);
+    }
+}
+
+struct Inliner<'a> {
+    program: &'a Program,
+}
+
+impl<'a> Inliner<'a> {
+    fn inlined_body(&self, id: &FuncId, args: &[Rc<Expr>]) -> Option<Expr> {
+        let callee = self.program.get(id)?;
+        // Constant parameters cannot be substituted as they are not arguments of the call.
+        match callee.ty {
+            Ty::Func(_, ref params) if params.is_empty() => (),
+            _ => return None,
+        }
+        if self.program.is_recursive(id) {
+            return None;
+        }
+
+        let mut body = callee.body.clone();
+        body.inline(self.program);
+        Substitution { args }.visit_mut_expr(&mut body);
+        Some(body)
+    }
+}
+
+impl<'a> VisitorMut for Inliner<'a> {
+    fn visit_mut_expr(&mut self, expr: &mut Expr) {
+        self.super_mut_expr(expr);
+
+        if let Expr::Apply(func, args) = expr {
+            if let Expr::Value(Value::Function(id, _)) = func.as_ref() {
+                if let Some(body) = self.inlined_body(id, args) {
+                    *expr = body;
+                }
+            }
+        }
+    }
+}
+
+/// Replaces the arguments of a function body by the expressions passed to it. The substitution
+/// is done in a single pass so the arguments of the caller are not confused with the ones of the
+/// callee.
+struct Substitution<'a> {
+    args: &'a [Rc<Expr>],
+}
+
+impl<'a> VisitorMut for Substitution<'a> {
+    fn visit_mut_expr(&mut self, expr: &mut Expr) {
+        if let Expr::Value(Value::Arg(index, _)) = *expr {
+            *expr = Expr::clone(&self.args[index - 1]);
+        } else {
+            self.super_mut_expr(expr);
+        }
+    }
+}
@@ -0,0 +1,120 @@
+use crate::optimize::fold;
+
+use super::*;
+
+/// Calls nested deeper than this are assumed not to terminate.
+const MAX_DEPTH: usize = 256;
+
+impl Program {
+    /// Evaluates the function with the given concrete inputs, which are its arguments followed by
+    /// its const parameters.
+    ///
+    /// Returns `None` if the function does not return a single constant, or if the evaluation
+    /// gets stuck: a division by zero, a failed assertion, a call to a function outside of the
+    /// program or more than 256 nested calls.
+    pub fn call(&self, id: &FuncId, inputs: &[Value]) -> Option<Value> {
+        let func = self.get(id)?;
+        let (args_len, params) = match &func.ty {
+            Ty::Func(args_ty, params) => (args_ty.len() - 1, params),
+            _ => unreachable!(),
+        };
+        if inputs.len() != args_len + params.len() {
+            return None;
+        }
+        let (args, param_values) = inputs.split_at(args_len);
+        let params = params
+            .iter()
+            .map(|Param(index, _)| *index)
+            .zip(param_values.iter().cloned())
+            .collect::<Vec<_>>();
+        match self.call_expr(id, args, &params, 0)? {
+            Expr::Value(value @ Value::Const(..)) => Some(value),
+            _ => None,
+        }
+    }
+
+    fn call_expr(
+        &self,
+        id: &FuncId,
+        args: &[Value],
+        params: &[(usize, Value)],
+        depth: usize,
+    ) -> Option<Expr> {
+        if depth > MAX_DEPTH {
+            return None;
+        }
+        Interpreter { program: self, args, params, depth }.eval(&self.get(id)?.body)
+    }
+}
+
+struct Interpreter<'a> {
+    program: &'a Program,
+    args: &'a [Value],
+    /// Const parameters are shared by the caller and the callee, so they are given by index.
+    params: &'a [(usize, Value)],
+    depth: usize,
+}
+
+impl<'a> Interpreter<'a> {
+    /// Evaluates the expression to a constant, a function or a tuple of those.
+    fn eval(&self, expr: &Expr) -> Option<Expr> {
+        match expr {
+            Expr::Value(Value::Arg(index, _)) => {
+                Some(Expr::Value(self.args.get(index - 1)?.clone()))
+            }
+            Expr::Value(Value::ConstParam(Param(index, _))) => {
+                let (_, value) = self.params.iter().find(|(param, _)| param == index)?;
+                Some(Expr::Value(value.clone()))
+            }
+            Expr::Value(value) => Some(Expr::Value(value.clone())),
+            Expr::Apply(func, args) => {
+                let id = match self.eval(func)? {
+                    Expr::Value(Value::Function(id, _)) => id,
+                    _ => return None,
+                };
+                let args = args
+                    .iter()
+                    .map(|arg| match self.eval(arg)? {
+                        Expr::Value(value) => Some(value),
+                        _ => None,
+                    })
+                    .collect::<Option<Vec<_>>>()?;
+                self.program.call_expr(&id, &args, self.params, self.depth + 1)
+            }
+            Expr::BinaryOp(bin_op, e1, e2) => {
+                let (a, ty) = self.eval_const(e1)?;
+                let (b, _) = self.eval_const(e2)?;
+                fold(*bin_op, a, b, ty).map(Expr::Value)
+            }
+            Expr::Switch(discr, values, results) => {
+                let (discr, _) = self.eval_const(discr)?;
+                for (value, result) in values.iter().zip(results) {
+                    if self.eval_const(value)?.0 == discr {
+                        return self.eval(result);
+                    }
+                }
+                self.eval(results.last()?)
+            }
+            Expr::Tuple(fields) => {
+                let fields = fields.iter().map(|field| self.eval(field).map(Rc::new));
+                fields.collect::<Option<_>>().map(Expr::Tuple)
+            }
+            Expr::Projection(tuple, index) => match self.eval(tuple)? {
+                Expr::Tuple(fields) => fields.get(*index).map(|field| Expr::clone(field)),
+                _ => None,
+            },
+            Expr::Assert(condition, result) => match self.eval_const(condition)? {
+                (0, _) => None,
+                _ => self.eval(result),
+            },
+            Expr::Abort(_) | Expr::Uninitialized => None,
+        }
+    }
+
+    fn eval_const(&self, expr: &Expr) -> Option<(u128, Ty)> {
+        match self.eval(expr)? {
+            Expr::Value(Value::Const(value, ty)) => Some((value, ty)),
+            _ => None,
+        }
+    }
+}
@@ -0,0 +1,108 @@
+use std::cmp::Ordering;
+use std::fmt;
+use std::rc::Rc;
+
+use serde::Serialize;
+
+pub use self::analysis::Path;
+pub use self::arena::*;
+pub use self::parse::*;
+pub use self::program::*;
+pub use self::symbols::*;
+pub use self::ty::*;
+pub use self::visitor::*;
+pub use self::visitor_mut::*;
+
+mod analysis;
+mod arena;
+mod display;
+mod inline;
+mod interpret;
+mod optimize;
+mod parse;
+mod program;
+mod symbols;
+mod ty;
+mod visitor;
+mod visitor_mut;
+
+/// The identifier of a function, which is also the name used for it in the textual and SMT-LIB
+/// representations.
+#[derive(Clone, Debug, PartialEq, Eq, PartialOrd, Ord, Hash, Serialize)]
+pub struct FuncId(pub String);
+
+#[derive(Clone, Debug, PartialEq, Eq)]
+pub struct FuncDef {
+    pub id: FuncId,
+    pub body: Expr,
+    pub ty: Ty,
+}
+
+impl FuncDef {
+    /// Returns `true` if the function calls itself directly. Mutually recursive functions are
+    /// found using `Program::is_recursive` instead.
+    pub fn is_recursive(&self) -> bool {
+        self.body.find_callees().contains(&self.id)
+    }
+}
+
+#[derive(Clone, Debug, PartialEq, Eq, Hash, Serialize)]
+pub struct Param(pub usize, pub Ty);
+
+impl Ord for Param {
+    fn cmp(&self, other: &Self) -> Ordering {
+        let Param(a, _) = self;
+        let Param(b, _) = other;
+        a.cmp(b)
+    }
+}
+
+impl PartialOrd for Param {
+    fn partial_cmp(&self, other: &Self) -> Option<Ordering> {
+        Some(self.cmp(other))
+    }
+}
+
+#[derive(Clone, Copy, Debug, PartialEq, Eq, Hash, Serialize)]
+pub enum BinOp {
+    Add,
+    Sub,
+    Mul,
+    Div,
+    Rem,
+    BitXor,
+    BitAnd,
+    BitOr,
+    Shl,
+    Shr,
+    Eq,
+    Lt,
+    Le,
+    Ne,
+    Ge,
+    Gt,
+}
+
+/// An expression of the SIR. Subexpressions are reference counted so an expression used in
+/// several places is shared instead of copied.
+#[derive(Clone, Debug, PartialEq, Eq, Serialize)]
+pub enum Expr {
+    Value(Value),
+    Apply(Rc<Expr>, Vec<Rc<Expr>>),
+    BinaryOp(BinOp, Rc<Expr>, Rc<Expr>),
+    Switch(Rc<Expr>, Vec<Rc<Expr>>, Vec<Rc<Expr>>),
+    Tuple(Vec<Rc<Expr>>),
+    Projection(Rc<Expr>, usize),
+    Assert(Rc<Expr>, Rc<Expr>),
+    /// The evaluation stops without a result, like when the function panics.
+    Abort(Ty),
+    Uninitialized,
+}
+
+#[derive(Clone, Debug, PartialEq, Eq, Hash, Serialize)]
+pub enum Value {
+    Arg(usize, Ty),
+    Const(u128, Ty),
+    Function(FuncId, Ty),
+    ConstParam(Param),
+}
@@ -0,0 +1,243 @@
+use std::collections::HashMap;
+
+use super::*;
+
+impl Expr {
+    /// Simplifies the expression by rewriting it bottom-up until no rule applies.
+    ///
+    /// Constants are folded with the wrapping semantics of bit-vectors, except for divisions by
+    /// zero and shifts larger than the width of the type, which are kept as they are. Rules that
+    /// would drop a subexpression only apply if it does not contain an assertion or an abort.
+    pub fn optimize(&mut self) {
+        let mut arena = ExprArena::default();
+        let root = arena.insert(self);
+        let root = arena.optimize(root);
+        *self = arena.expr(root);
+    }
+}
+
+impl ExprArena {
+    /// Simplifies the expression with the given root like `Expr::optimize` does, returning the
+    /// root of the result. Every subexpression is simplified once, however often it is used.
+    pub fn optimize(&mut self, root: ExprId) -> ExprId {
+        Optimizer { arena: self, optimized: HashMap::new(), pure: HashMap::new() }.optimize(root)
+    }
+}
+
+struct Optimizer<'a> {
+    arena: &'a mut ExprArena,
+    optimized: HashMap<ExprId, ExprId>,
+    pure: HashMap<ExprId, bool>,
+}
+
+impl<'a> Optimizer<'a> {
+    fn optimize(&mut self, id: ExprId) -> ExprId {
+        if let Some(&optimized) = self.optimized.get(&id) {
+            return optimized;
+        }
+
+        let node = match self.arena.node(id).clone() {
+            Node::Apply(func, args) => Node::Apply(self.optimize(func), self.optimize_all(&args)),
+            Node::BinaryOp(bin_op, e1, e2) => {
+                Node::BinaryOp(bin_op, self.optimize(e1), self.optimize(e2))
+            }
+            Node::Switch(discr, values, results) => Node::Switch(
+                self.optimize(discr),
+                self.optimize_all(&values),
+                self.optimize_all(&results),
+            ),
+            Node::Tuple(fields) => Node::Tuple(self.optimize_all(&fields)),
+            Node::Projection(tuple, index) => Node::Projection(self.optimize(tuple), index),
+            Node::Assert(condition, result) => {
+                Node::Assert(self.optimize(condition), self.optimize(result))
+            }
+            node => node,
+        };
+        let mut optimized = self.arena.intern(node);
+        while let Some(simplified) = self.simplify(optimized) {
+            optimized = simplified;
+        }
+
+        self.optimized.insert(id, optimized);
+        optimized
+    }
+
+    fn optimize_all(&mut self, ids: &[ExprId]) -> Vec<ExprId> {
+        ids.iter().map(|&id| self.optimize(id)).collect()
+    }
+
+    /// Applies a single rewriting rule to the root of the expression.
+    fn simplify(&mut self, id: ExprId) -> Option<ExprId> {
+        match self.arena.node(id).clone() {
+            Node::Projection(tuple, index) => match self.arena.node(tuple).clone() {
+                Node::Tuple(fields) if fields.iter().all(|&field| self.is_pure(field)) => {
+                    fields.get(index).copied()
+                }
+                _ => None,
+            },
+            Node::BinaryOp(bin_op, e1, e2) => self.simplify_bin_op(bin_op, e1, e2),
+            Node::Switch(discr, values, results) => self.simplify_switch(discr, &values, &results),
+            _ => None,
+        }
+    }
+
+    fn simplify_bin_op(&mut self, bin_op: BinOp, e1: ExprId, e2: ExprId) -> Option<ExprId> {
+        use BinOp::*;
+
+        if let (Some((a, ty)), Some((b, _))) = (self.as_const(e1), self.as_const(e2)) {
+            return fold(bin_op, a, b, ty).map(|value| self.arena.intern(Node::Value(value)));
+        }
+
+        // Keep constants on the right.
+        if self.as_const(e1).is_some() {
+            let mirrored = match bin_op {
+                Add | Mul | BitXor | BitAnd | BitOr | Eq | Ne => bin_op,
+                Lt => Gt,
+                Le => Ge,
+                Gt => Lt,
+                Ge => Le,
+                Sub | Div | Rem | Shl | Shr => return None,
+            };
+            return Some(self.arena.intern(Node::BinaryOp(mirrored, e2, e1)));
+        }
+
+        let ty = self.arena.ty(e1);
+        let rhs = self.as_const(e2).map(|(b, _)| b);
+
+        match (bin_op, rhs) {
+            (Add, Some(0)) | (Sub, Some(0)) | (BitXor, Some(0)) | (BitOr, Some(0)) => {
+                return Some(e1)
+            }
+            (Shl, Some(0)) | (Shr, Some(0)) => return Some(e1),
+            (Mul, Some(1)) | (Div, Some(1)) => return Some(e1),
+            (Mul, Some(0)) | (BitAnd, Some(0)) if self.is_pure(e1) => {
+                return Some(self.constant(0, ty))
+            }
+            // Comparisons with `true` and `false`.
+            (Eq, Some(1)) | (Ne, Some(0)) if ty == Ty::Bool => return Some(e1),
+            _ => (),
+        }
+
+        // Equal expressions have the same id.
+        if e1 == e2 && self.is_pure(e1) {
+            return match bin_op {
+                Sub | BitXor => Some(self.constant(0, ty)),
+                BitAnd | BitOr => Some(e1),
+                Eq | Le | Ge => Some(self.constant(1, Ty::Bool)),
+                Ne | Lt | Gt => Some(self.constant(0, Ty::Bool)),
+                _ => None,
+            };
+        }
+
+        None
+    }
+
+    fn simplify_switch(
+        &mut self,
+        discr: ExprId,
+        values: &[ExprId],
+        results: &[ExprId],
+    ) -> Option<ExprId> {
+        if let Some((discr, _)) = self.as_const(discr) {
+            let index = values
+                .iter()
+                .position(|&value| self.as_const(value).map(|(value, _)| value) == Some(discr))
+                .unwrap_or(values.len());
+            return results.get(index).copied();
+        }
+
+        // Every branch gives the same result.
+        if self.is_pure(discr) && results.iter().all(|&result| result == results[0]) {
+            return Some(results[0]);
+        }
+
+        None
+    }
+
+    fn as_const(&self, id: ExprId) -> Option<(u128, Ty)> {
+        match self.arena.node(id) {
+            Node::Value(Value::Const(value, ty)) => Some((*value, ty.clone())),
+            _ => None,
+        }
+    }
+
+    fn constant(&mut self, value: u128, ty: Ty) -> ExprId {
+        self.arena.intern(Node::Value(Value::Const(value, ty)))
+    }
+
+    /// Returns `true` if the expression can be dropped without losing an assertion or an abort.
+    fn is_pure(&mut self, id: ExprId) -> bool {
+        if let Some(&pure) = self.pure.get(&id) {
+            return pure;
+        }
+        let pure = match self.arena.node(id) {
+            Node::Assert(..) | Node::Abort(_) => false,
+            node => node.children().into_iter().all(|child| self.is_pure(child)),
+        };
+        self.pure.insert(id, pure);
+        pure
+    }
+}
+
+/// Folds an operation between two constants of type `ty`.
+pub(crate) fn fold(bin_op: BinOp, a: u128, b: u128, ty: Ty) -> Option<Value> {
+    use BinOp::*;
+
+    let (bits, signed) = match ty {
+        Ty::Bool => (1, false),
+        Ty::Int(bits) => (bits, true),
+        Ty::Uint(bits) => (bits, false),
+        _ => return None,
+    };
+    let (sa, sb) = (sign_extend(a, bits), sign_extend(b, bits));
+
+    let value = match bin_op {
+        Add => a.wrapping_add(b),
+        Sub => a.wrapping_sub(b),
+        Mul => a.wrapping_mul(b),
+        Div | Rem if b == 0 => return None,
+        Div if signed => sa.wrapping_div(sb) as u128,
+        Div => a / b,
+        Rem if signed => sa.wrapping_rem(sb) as u128,
+        Rem => a % b,
+        BitXor => a ^ b,
+        BitAnd => a & b,
+        BitOr => a | b,
+        Shl | Shr if b >= bits as u128 => return None,
+        Shl => a << b,
+        Shr if signed => (sa >> b) as u128,
+        Shr => a >> b,
+        Eq => return Some(Value::Const((a == b) as u128, Ty::Bool)),
+        Ne => return Some(Value::Const((a != b) as u128, Ty::Bool)),
+        Lt | Le | Gt | Ge => {
+            let ordering = if signed { sa.cmp(&sb) } else { a.cmp(&b) };
+            let result = match bin_op {
+                Lt => ordering == Ordering::Less,
+                Le => ordering != Ordering::Greater,
+                Gt => ordering == Ordering::Greater,
+                _ => ordering != Ordering::Less,
+            };
+            return Some(Value::Const(result as u128, Ty::Bool));
+        }
+    };
+
+    Some(Value::Const(value & mask(bits), ty))
+}
+
+// The associated constants of the integer types are too recent for the toolchain of the evaluator.
+#[allow(clippy::legacy_numeric_constants)]
+fn mask(bits: usize) -> u128 {
+    if bits >= 128 {
+        std::u128::MAX
+    } else {
+        (1 << bits) - 1
+    }
+}
+
+fn sign_extend(value: u128, bits: usize) -> i128 {
+    if bits >= 128 {
+        value as i128
+    } else {
+        ((value << (128 - bits)) as i128) >> (128 - bits)
+    }
+}
@@ -0,0 +1,366 @@
+use std::collections::BTreeMap;
+use std::iter::Peekable;
+use std::vec::IntoIter;
+
+use super::*;
+
+pub type ParseResult<T> = Result<T, ParseError>;
+
+#[derive(Clone, Debug, PartialEq, Eq)]
+pub struct ParseError(pub String);
+
+impl fmt::Display for ParseError {
+    fn fmt(&self, f: &mut fmt::Formatter<'_>) -> fmt::Result {
+        write!(f, "{}", self.0)
+    }
+}
+
+impl std::error::Error for ParseError {}
+
+macro_rules! parse_error {
+    ($($arg:tt)*) => {
+        Err(ParseError(format!($($arg)*)))
+    };
+}
+
+/// Parses a sequence of function definitions written in the syntax used by the `Display`
+/// implementation of `FuncDef`.
+pub fn parse_func_defs(input: &str) -> ParseResult<Vec<FuncDef>> {
+    let mut exprs = Vec::new();
+    let mut tokens = tokenize(input).into_iter().peekable();
+    while tokens.peek().is_some() {
+        exprs.push(SExpr::parse(&mut tokens)?);
+    }
+
+    let mut parser = Parser::default();
+    // Collect all the signatures first so functions can be called before being defined.
+    let headers =
+        exprs.iter().map(|expr| parser.parse_header(expr)).collect::<ParseResult<Vec<_>>>()?;
+    exprs.iter().zip(headers).map(|(expr, id)| parser.parse_func_def(expr, id)).collect()
+}
+
+#[derive(Clone, Debug, PartialEq, Eq)]
+enum Token {
+    Open,
+    Close,
+    OpenBracket,
+    CloseBracket,
+    Comma,
+    Atom(String),
+}
+
+fn tokenize(input: &str) -> Vec<Token> {
+    let mut tokens = Vec::new();
+    let mut chars = input.chars().peekable();
+    while let Some(&c) = chars.peek() {
+        let token = match c {
+            '(' => Token::Open,
+            ')' => Token::Close,
+            '[' => Token::OpenBracket,
+            ']' => Token::CloseBracket,
+            ',' => Token::Comma,
+            c if c.is_whitespace() => {
+                chars.next();
+                continue;
+            }
+            _ => {
+                let mut atom = String::new();
+                while let Some(&c) = chars.peek() {
+                    if c.is_whitespace() || "()[],".contains(c) {
+                        break;
+                    }
+                    atom.push(c);
+                    chars.next();
+                }
+                tokens.push(Token::Atom(atom));
+                continue;
+            }
+        };
+        tokens.push(token);
+        chars.next();
+    }
+    tokens
+}
+
+#[derive(Clone, Debug)]
+enum SExpr {
+    Atom(String),
+    List(Vec<SExpr>),
+    Bracket(Vec<SExpr>),
+    Comma,
+}
+
+impl SExpr {
+    fn parse(tokens: &mut Peekable<IntoIter<Token>>) -> ParseResult<Self> {
+        match tokens.next() {
+            Some(Token::Atom(atom)) => Ok(SExpr::Atom(atom)),
+            Some(Token::Comma) => Ok(SExpr::Comma),
+            Some(Token::Open) => Self::parse_seq(tokens, Token::Close).map(SExpr::List),
+            Some(Token::OpenBracket) => {
+                Self::parse_seq(tokens, Token::CloseBracket).map(SExpr::Bracket)
+            }
+            Some(token) => parse_error!("Unexpected token {:?}", token),
+            None => parse_error!("Unexpected end of input"),
+        }
+    }
+
+    fn parse_seq(tokens: &mut Peekable<IntoIter<Token>>, close: Token) -> ParseResult<Vec<Self>> {
+        let mut seq = Vec::new();
+        loop {
+            match tokens.peek() {
+                Some(token) if *token == close => {
+                    tokens.next();
+                    return Ok(seq);
+                }
+                Some(_) => seq.push(Self::parse(tokens)?),
+                None => return parse_error!("Unclosed delimiter, expected {:?}", close),
+            }
+        }
+    }
+
+    fn atom(&self) -> Option<&str> {
+        match self {
+            SExpr::Atom(atom) => Some(atom),
+            _ => None,
+        }
+    }
+}
+
+impl fmt::Display for SExpr {
+    fn fmt(&self, f: &mut fmt::Formatter<'_>) -> fmt::Result {
+        let join = |exprs: &[SExpr]| exprs.iter().map(|e| e.to_string()).collect::<Vec<_>>();
+        match self {
+            SExpr::Atom(atom) => write!(f, "{}", atom),
+            SExpr::List(exprs) => write!(f, "({})", join(exprs).join(" ")),
+            SExpr::Bracket(exprs) => write!(f, "[{}]", join(exprs).join(" ")),
+            SExpr::Comma => write!(f, ","),
+        }
+    }
+}
+
+#[derive(Default)]
+struct Parser {
+    signatures: BTreeMap<FuncId, Ty>,
+}
+
+/// The context used to parse the body of a function.
+struct Scope<'a> {
+    args_ty: &'a [Ty],
+    params: &'a [Param],
+}
+
+impl Parser {
+    fn parse_header(&mut self, expr: &SExpr) -> ParseResult<FuncId> {
+        match expr {
+            SExpr::List(items) => match items.as_slice() {
+                [SExpr::Atom(defun), name, SExpr::Bracket(params), SExpr::List(args_ty), _]
+                    if defun == "defun" =>
+                {
+                    let id = match name.atom() {
+                        Some(name) => FuncId(name.to_owned()),
+                        None => return parse_error!("Expected function name, found {}", name),
+                    };
+                    let params = params.iter().map(parse_param).collect::<ParseResult<_>>()?;
+                    let args_ty = args_ty.iter().map(parse_ty).collect::<ParseResult<Vec<_>>>()?;
+                    if args_ty.is_empty() {
+                        return parse_error!("Function {} has no return type", name);
+                    }
+                    if self.signatures.insert(id.clone(), Ty::Func(args_ty, params)).is_some() {
+                        return parse_error!("Function {} is defined twice", name);
+                    }
+                    Ok(id)
+                }
+                _ => parse_error!("Expected function definition, found {}", expr),
+            },
+            _ => parse_error!("Expected function definition, found {}", expr),
+        }
+    }
+
+    fn parse_func_def(&mut self, expr: &SExpr, id: FuncId) -> ParseResult<FuncDef> {
+        let ty = self.signatures[&id].clone();
+        let body = match (expr, &ty) {
+            (SExpr::List(items), Ty::Func(args_ty, params)) => {
+                self.parse_expr(items.last().unwrap(), &Scope { args_ty, params })?
+            }
+            _ => unreachable!(),
+        };
+        Ok(FuncDef { id, body, ty })
+    }
+
+    fn parse_expr(&mut self, expr: &SExpr, scope: &Scope<'_>) -> ParseResult<Expr> {
+        match expr {
+            SExpr::Atom(atom) => self.parse_atom(atom, scope),
+            SExpr::List(items) => match items.as_slice() {
+                [] => parse_error!("Unexpected ()"),
+                [SExpr::Atom(head), ty, value] if head == "const" => {
+                    let value = match value.atom().and_then(|value| value.parse().ok()) {
+                        Some(value) => value,
+                        None => return parse_error!("Expected constant value, found {}", value),
+                    };
+                    Ok(Expr::Value(Value::Const(value, parse_ty(ty)?)))
+                }
+                _ if items.len() >= 2 && items[0].atom() == Some("switch") => {
+                    let discr = self.parse_expr(&items[1], scope)?;
+                    let branches = &items[2..];
+                    let mut values = Vec::new();
+                    let mut targets = Vec::new();
+                    for (i, branch) in branches.iter().enumerate() {
+                        let is_else = i + 1 == branches.len();
+                        match branch {
+                            SExpr::List(arm) => match arm.as_slice() {
+                                [SExpr::Atom(value), SExpr::Atom(arrow), target]
+                                    if is_else && value == "else" && arrow == "->" =>
+                                {
+                                    targets.push(Rc::new(self.parse_expr(target, scope)?));
+                                }
+                                [value, SExpr::Atom(arrow), target]
+                                    if !is_else && arrow == "->" =>
+                                {
+                                    values.push(Rc::new(self.parse_expr(value, scope)?));
+                                    targets.push(Rc::new(self.parse_expr(target, scope)?));
+                                }
+                                _ => return parse_error!("Invalid switch branch {}", branch),
+                            },
+                            _ => return parse_error!("Invalid switch branch {}", branch),
+                        }
+                    }
+                    if targets.is_empty() {
+                        return parse_error!("Switch {} has no else branch", expr);
+                    }
+                    Ok(Expr::Switch(Rc::new(discr), values, targets))
+                }
+                _ if items[0].atom() == Some("tuple") => Ok(Expr::Tuple(
+                    items[1..]
+                        .iter()
+                        .map(|e| self.parse_expr(e, scope).map(Rc::new))
+                        .collect::<ParseResult<_>>()?,
+                )),
+                [SExpr::Atom(head), tuple, SExpr::Atom(index)] if head == "proj" => {
+                    match index.parse() {
+                        Ok(index) => {
+                            Ok(Expr::Projection(Rc::new(self.parse_expr(tuple, scope)?), index))
+                        }
+                        Err(_) => parse_error!("Expected field index, found {}", index),
+                    }
+                }
+                [SExpr::Atom(head), condition, result] if head == "assert" => Ok(Expr::Assert(
+                    Rc::new(self.parse_expr(condition, scope)?),
+                    Rc::new(self.parse_expr(result, scope)?),
+                )),
+                [SExpr::Atom(head), ty] if head == "abort" => Ok(Expr::Abort(parse_ty(ty)?)),
+                [SExpr::Atom(head), e1, e2] if parse_bin_op(head).is_some() => Ok(Expr::BinaryOp(
+                    parse_bin_op(head).unwrap(),
+                    Rc::new(self.parse_expr(e1, scope)?),
+                    Rc::new(self.parse_expr(e2, scope)?),
+                )),
+                _ => Ok(Expr::Apply(
+                    Rc::new(self.parse_expr(&items[0], scope)?),
+                    items[1..]
+                        .iter()
+                        .map(|e| self.parse_expr(e, scope).map(Rc::new))
+                        .collect::<ParseResult<_>>()?,
+                )),
+            },
+            _ => parse_error!("Expected expression, found {}", expr),
+        }
+    }
+
+    fn parse_atom(&mut self, atom: &str, scope: &Scope<'_>) -> ParseResult<Expr> {
+        if atom == "uninitialized" {
+            return Ok(Expr::Uninitialized);
+        }
+
+        if let Some(index) = parse_index(atom, "_") {
+            return match scope.args_ty.get(index) {
+                Some(ty) if index > 0 => Ok(Expr::Value(Value::Arg(index, ty.clone()))),
+                _ => parse_error!("Argument {} is out of bounds", atom),
+            };
+        }
+
+        if let Some(index) = parse_index(atom, "p") {
+            return match scope.params.iter().find(|Param(i, _)| *i == index) {
+                Some(param) => Ok(Expr::Value(Value::ConstParam(param.clone()))),
+                None => parse_error!("Parameter {} is not declared", atom),
+            };
+        }
+
+        let id = FuncId(atom.to_owned());
+        match self.signatures.get(&id) {
+            // Function values do not carry the parameters of the function.
+            Some(Ty::Func(args_ty, _)) => {
+                Ok(Expr::Value(Value::Function(id, Ty::Func(args_ty.clone(), Vec::new()))))
+            }
+            _ => parse_error!("Function {} is not defined", atom),
+        }
+    }
+}
+
+fn parse_param(expr: &SExpr) -> ParseResult<Param> {
+    if let SExpr::List(items) = expr {
+        if let [SExpr::Atom(name), ty] = items.as_slice() {
+            if let Some(index) = parse_index(name, "p") {
+                return Ok(Param(index, parse_ty(ty)?));
+            }
+        }
+    }
+    parse_error!("Expected parameter, found {}", expr)
+}
+
+fn parse_ty(expr: &SExpr) -> ParseResult<Ty> {
+    match expr {
+        SExpr::Atom(atom) if atom == "bool" => Ok(Ty::Bool),
+        SExpr::List(items) => match items.as_slice() {
+            [SExpr::Atom(kind), SExpr::Atom(bits)] if kind == "int" || kind == "uint" => {
+                match bits.parse() {
+                    Ok(bits) if kind == "int" => Ok(Ty::Int(bits)),
+                    Ok(bits) => Ok(Ty::Uint(bits)),
+                    Err(_) => parse_error!("Expected bit width, found {}", bits),
+                }
+            }
+            _ => {
+                let mut fields_ty = Vec::new();
+                for (i, item) in items.iter().enumerate() {
+                    match item {
+                        SExpr::Comma if i % 2 == 1 => (),
+                        item if i % 2 == 0 => fields_ty.push(parse_ty(item)?),
+                        _ => return parse_error!("Expected type, found {}", expr),
+                    }
+                }
+                Ok(Ty::Tuple(fields_ty))
+            }
+        },
+        _ => parse_error!("Expected type, found {}", expr),
+    }
+}
+
+// `str::strip_prefix` is too recent for the toolchain of the evaluator.
+#[allow(clippy::manual_strip)]
+fn parse_index(atom: &str, prefix: &str) -> Option<usize> {
+    if atom.starts_with(prefix) {
+        atom[prefix.len()..].parse().ok()
+    } else {
+        None
+    }
+}
+
+fn parse_bin_op(op: &str) -> Option<BinOp> {
+    Some(match op {
+        "+" => BinOp::Add,
+        "-" => BinOp::Sub,
+        "*" => BinOp::Mul,
+        "/" => BinOp::Div,
+        "%" => BinOp::Rem,
+        "^" => BinOp::BitXor,
+        "&" => BinOp::BitAnd,
+        "|" => BinOp::BitOr,
+        "<<" => BinOp::Shl,
+        ">>" => BinOp::Shr,
+        "=" => BinOp::Eq,
+        "<" => BinOp::Lt,
+        "<=" => BinOp::Le,
+        "!=" => BinOp::Ne,
+        ">=" => BinOp::Ge,
+        ">" => BinOp::Gt,
+        _ => return None,
+    })
+}
@@ -0,0 +1,199 @@
+use std::collections::VecDeque;
+
+use super::*;
+
+/// A set of functions together with their call graph.
+///
+/// Calls to functions that are not part of the program are kept in the bodies but are not edges
+/// of the call graph; `undefined_callees` lists them.
+#[derive(Clone, Debug, Default)]
+pub struct Program {
+    funcs: Vec<FuncDef>,
+    symbols: SymbolTable,
+    callees: Vec<Vec<usize>>,
+}
+
+impl Program {
+    pub fn new(funcs: Vec<FuncDef>, symbols: SymbolTable) -> Self {
+        let callees = funcs
+            .iter()
+            .map(|func| {
+                func.body
+                    .find_callees()
+                    .iter()
+                    .filter_map(|id| funcs.iter().position(|f| f.id == *id))
+                    .collect()
+            })
+            .collect();
+
+        Program { funcs, symbols, callees }
+    }
+
+    pub fn funcs(&self) -> &[FuncDef] {
+        &self.funcs
+    }
+
+    pub fn symbols(&self) -> &SymbolTable {
+        &self.symbols
+    }
+
+    pub fn get(&self, id: &FuncId) -> Option<&FuncDef> {
+        self.index(id).map(|i| &self.funcs[i])
+    }
+
+    /// Returns the path of the function if the symbol table has it, and its identifier otherwise.
+    pub fn name(&self, id: &FuncId) -> String {
+        self.symbols.name(id)
+    }
+
+    fn index(&self, id: &FuncId) -> Option<usize> {
+        self.funcs.iter().position(|func| func.id == *id)
+    }
+
+    /// Returns the functions of the program called directly by the given function.
+    pub fn callees(&self, id: &FuncId) -> Vec<&FuncDef> {
+        self.index(id)
+            .map(|i| self.callees[i].iter().map(|&j| &self.funcs[j]).collect())
+            .unwrap_or_default()
+    }
+
+    /// Returns the functions of the program calling the given function directly.
+    pub fn callers(&self, id: &FuncId) -> Vec<&FuncDef> {
+        match self.index(id) {
+            Some(i) => (0..self.funcs.len())
+                .filter(|&j| self.callees[j].contains(&i))
+                .map(|j| &self.funcs[j])
+                .collect(),
+            None => Vec::new(),
+        }
+    }
+
+    /// Returns the functions called by the given function that are not part of the program.
+    pub fn undefined_callees(&self, id: &FuncId) -> Vec<FuncId> {
+        self.get(id)
+            .map(|func| {
+                func.body.find_callees().into_iter().filter(|id| self.index(id).is_none()).collect()
+            })
+            .unwrap_or_default()
+    }
+
+    /// Returns `true` if the function can call itself, directly or through other functions.
+    pub fn is_recursive(&self, id: &FuncId) -> bool {
+        match self.index(id) {
+            Some(i) => self.reachable_from(&self.callees[i]).contains(&i),
+            None => false,
+        }
+    }
+
+    /// Splits the program into the strongly connected components of its call graph, so mutually
+    /// recursive functions end up in the same component. Every component comes after the
+    /// components it calls and the functions of a component keep the order of the program.
+    pub fn components(&self) -> Vec<Vec<&FuncDef>> {
+        let mut tarjan = Tarjan::new(&self.callees);
+        for node in 0..self.funcs.len() {
+            if tarjan.index[node].is_none() {
+                tarjan.visit(node);
+            }
+        }
+        tarjan
+            .components
+            .into_iter()
+            .map(|component| component.into_iter().map(|i| &self.funcs[i]).collect())
+            .collect()
+    }
+
+    /// Returns every function of the program after the functions it calls, except for the
+    /// functions in the same component.
+    pub fn topological_order(&self) -> Vec<&FuncDef> {
+        self.components().into_iter().flatten().collect()
+    }
+
+    /// Returns the functions that can be called, directly or not, from any of the given ones.
+    pub fn reachable(&self, roots: &[&FuncId]) -> Vec<&FuncDef> {
+        let roots = roots.iter().filter_map(|id| self.index(id)).collect::<Vec<_>>();
+        let mut reachable = self.reachable_from(&roots);
+        reachable.extend(roots);
+        reachable.sort();
+        reachable.dedup();
+        reachable.into_iter().map(|i| &self.funcs[i]).collect()
+    }
+
+    /// Returns the program made of the given functions and every function they call.
+    pub fn restrict(&self, roots: &[&FuncId]) -> Program {
+        let funcs = self.reachable(roots).into_iter().cloned().collect();
+        Program::new(funcs, self.symbols.clone())
+    }
+
+    /// The nodes reachable through at least one edge from the given ones.
+    fn reachable_from(&self, nodes: &[usize]) -> Vec<usize> {
+        let mut queue =
+            nodes.iter().flat_map(|&i| self.callees[i].clone()).collect::<VecDeque<_>>();
+        let mut reachable = Vec::new();
+        while let Some(node) = queue.pop_front() {
+            if !reachable.contains(&node) {
+                reachable.push(node);
+                queue.extend(&self.callees[node]);
+            }
+        }
+        reachable
+    }
+}
+
+/// Tarjan's algorithm, which emits each component after all the components reachable from it.
+struct Tarjan<'a> {
+    callees: &'a [Vec<usize>],
+    index: Vec<Option<usize>>,
+    low_link: Vec<usize>,
+    on_stack: Vec<bool>,
+    stack: Vec<usize>,
+    next_index: usize,
+    components: Vec<Vec<usize>>,
+}
+
+impl<'a> Tarjan<'a> {
+    fn new(callees: &'a [Vec<usize>]) -> Self {
+        Tarjan {
+            callees,
+            index: vec![None; callees.len()],
+            low_link: vec![0; callees.len()],
+            on_stack: vec![false; callees.len()],
+            stack: Vec::new(),
+            next_index: 0,
+            components: Vec::new(),
+        }
+    }
+
+    fn visit(&mut self, node: usize) {
+        self.index[node] = Some(self.next_index);
+        self.low_link[node] = self.next_index;
+        self.next_index += 1;
+        self.stack.push(node);
+        self.on_stack[node] = true;
+
+        for &callee in &self.callees[node] {
+            match self.index[callee] {
+                None => {
+                    self.visit(callee);
+                    self.low_link[node] = self.low_link[node].min(self.low_link[callee]);
+                }
+                Some(index) if self.on_stack[callee] => {
+                    self.low_link[node] = self.low_link[node].min(index);
+                }
+                Some(_) => (),
+            }
+        }
+
+        if Some(self.low_link[node]) == self.index[node] {
+            let mut component = Vec::new();
+            while let Some(member) = self.stack.pop() {
+                self.on_stack[member] = false;
+                component.push(member);
+                if member == node {
+                    break;
+                }
+            }
+            component.sort();
+            self.components.push(component);
+        }
+    }
+}
@@ -0,0 +1,110 @@
+use std::collections::BTreeMap;
+
+use super::*;
+
+/// Maps the identifier of each function to the path of the item it was evaluated from.
+///
+/// Identifiers are sanitized versions of the paths, so they can be used both in the textual
+/// representation of SIR and as SMT-LIB symbols.
+#[derive(Clone, Debug, Default, PartialEq, Eq)]
+pub struct SymbolTable {
+    paths: BTreeMap<FuncId, String>,
+}
+
+impl SymbolTable {
+    /// Returns the identifier for the given path, creating a new one if the path is not in the
+    /// table yet.
+    pub fn insert(&mut self, path: &str) -> FuncId {
+        if let Some((id, _)) = self.paths.iter().find(|(_, p)| *p == path) {
+            return id.clone();
+        }
+
+        let name = sanitize(path);
+        let mut id = FuncId(name.clone());
+        let mut suffix = 0;
+        while is_reserved(&id.0) || self.paths.contains_key(&id) {
+            suffix += 1;
+            id = FuncId(format!("{}!{}", name, suffix));
+        }
+
+        self.paths.insert(id.clone(), path.to_owned());
+        id
+    }
+
+    pub fn path(&self, id: &FuncId) -> Option<&str> {
+        self.paths.get(id).map(String::as_str)
+    }
+
+    /// Returns the path of the function if it is known, and its identifier otherwise.
+    pub fn name(&self, id: &FuncId) -> String {
+        self.path(id).map(ToOwned::to_owned).unwrap_or_else(|| id.to_string())
+    }
+
+    pub fn find(&self, path: &str) -> Option<&FuncId> {
+        self.paths.iter().find(|(_, p)| *p == path).map(|(id, _)| id)
+    }
+
+    pub fn iter(&self) -> impl Iterator<Item = (&FuncId, &str)> {
+        self.paths.iter().map(|(id, path)| (id, path.as_str()))
+    }
+}
+
+/// Replaces the `::` separators by dots and any character that is not allowed in a SMT-LIB
+/// symbol by an underscore.
+fn sanitize(path: &str) -> String {
+    path.replace("::", ".")
+        .chars()
+        .map(|c| if c.is_ascii_alphanumeric() || "~!@$%^&*_-+=<>.?/".contains(c) { c } else { '_' })
+        .collect()
+}
+
+/// Names used by the textual representation of SIR or by the SMT-LIB encoding.
+fn is_reserved(name: &str) -> bool {
+    const KEYWORDS: &[&str] = &[
+        "defun",
+        "switch",
+        "tuple",
+        "proj",
+        "assert",
+        "abort",
+        "const",
+        "uninitialized",
+        "else",
+        "bool",
+        "int",
+        "uint",
+        "ite",
+        "and",
+        "or",
+        "not",
+        "xor",
+        "distinct",
+        "let",
+        "forall",
+        "exists",
+        "par",
+        "match",
+        "as",
+        "true",
+        "false",
+        "first",
+        "second",
+        "unit",
+        "Tuple",
+        "Unit",
+        "Bool",
+        "_",
+    ];
+    let is_indexed = |prefix: &str| {
+        name.starts_with(prefix)
+            && name.len() > prefix.len()
+            && name[prefix.len()..].chars().all(|c| c.is_ascii_digit())
+    };
+
+    KEYWORDS.contains(&name)
+        || name.starts_with("bv")
+        || name.starts_with(|c: char| c.is_ascii_digit())
+        || is_indexed("_")
+        || is_indexed("p")
+        || is_indexed("x")
+}
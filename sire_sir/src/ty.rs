@@ -1,6 +1,8 @@
+use serde::Serialize;
+
 use super::*;
 
-#[derive(Clone, Debug, PartialEq, Eq, Hash)]
+#[derive(Clone, Debug, PartialEq, Eq, Hash, Serialize)]
 pub enum Ty {
     Int(usize),
     Uint(usize),
@@ -55,11 +57,12 @@ impl Typed for Expr {
             },
             Expr::Switch(_, _, e1) => e1.first().unwrap().ty(),
             Expr::Tuple(e1) => Ty::Tuple(e1.iter().map(|e| e.ty()).collect()),
-            Expr::Projection(e1, i) => match **e1 {
-                Expr::Tuple(ref fields) => fields.get(*i).unwrap().ty(),
+            Expr::Projection(e1, i) => match e1.ty() {
+                Ty::Tuple(fields_ty) => fields_ty[*i].clone(),
                 _ => unreachable!(),
             },
             Expr::Assert(_, e1) => e1.ty(),
+            Expr::Abort(ty) => ty.clone(),
             Expr::Uninitialized => unreachable!(),
         }
     }
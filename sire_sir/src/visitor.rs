@@ -9,7 +9,7 @@ pub trait Visitor {
         self.super_value(value)
     }
 
-    fn visit_apply(&mut self, func: &Expr, args: &[Expr]) {
+    fn visit_apply(&mut self, func: &Expr, args: &[Rc<Expr>]) {
         self.super_apply(func, args)
     }
 
@@ -17,11 +17,11 @@ pub trait Visitor {
         self.super_binary_op(bin_op, e1, e2)
     }
 
-    fn visit_switch(&mut self, expr: &Expr, values: &[Expr], results: &[Expr]) {
+    fn visit_switch(&mut self, expr: &Expr, values: &[Rc<Expr>], results: &[Rc<Expr>]) {
         self.super_switch(expr, values, results)
     }
 
-    fn visit_tuple(&mut self, fields: &[Expr]) {
+    fn visit_tuple(&mut self, fields: &[Rc<Expr>]) {
         self.super_tuple(fields)
     }
 
@@ -42,15 +42,13 @@ pub trait Visitor {
             Expr::Tuple(e1) => self.visit_tuple(e1),
             Expr::Projection(e1, index) => self.visit_projection(e1, *index),
             Expr::Assert(e1, e2) => self.visit_assert(e1, e2),
-            Expr::Uninitialized => (),
+            Expr::Abort(_) | Expr::Uninitialized => (),
         }
     }
 
-    fn super_value(&mut self, _: &Value) {
-        ()
-    }
+    fn super_value(&mut self, _: &Value) {}
 
-    fn super_apply(&mut self, func: &Expr, args: &[Expr]) {
+    fn super_apply(&mut self, func: &Expr, args: &[Rc<Expr>]) {
         self.visit_expr(func);
         for arg in args {
             self.visit_expr(arg);
@@ -62,7 +60,7 @@ pub trait Visitor {
         self.visit_expr(e2);
     }
 
-    fn super_switch(&mut self, expr: &Expr, values: &[Expr], results: &[Expr]) {
+    fn super_switch(&mut self, expr: &Expr, values: &[Rc<Expr>], results: &[Rc<Expr>]) {
         self.visit_expr(expr);
         for value in values {
             self.visit_expr(value);
@@ -72,7 +70,7 @@ pub trait Visitor {
         }
     }
 
-    fn super_tuple(&mut self, fields: &[Expr]) {
+    fn super_tuple(&mut self, fields: &[Rc<Expr>]) {
         for field in fields {
             self.visit_expr(field);
         }
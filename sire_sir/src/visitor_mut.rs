@@ -1,5 +1,7 @@
 use super::*;
 
+/// Visits an expression mutably. Shared subexpressions are copied before being visited, so the
+/// changes only affect the expression being visited.
 pub trait VisitorMut {
     fn visit_mut_expr(&mut self, expr: &mut Expr) {
         self.super_mut_expr(expr)
@@ -9,7 +11,7 @@ pub trait VisitorMut {
         self.super_mut_value(value)
     }
 
-    fn visit_mut_apply(&mut self, func: &mut Expr, args: &mut [Expr]) {
+    fn visit_mut_apply(&mut self, func: &mut Expr, args: &mut [Rc<Expr>]) {
         self.super_mut_apply(func, args)
     }
 
@@ -17,11 +19,16 @@ pub trait VisitorMut {
         self.super_mut_binary_op(bin_op, e1, e2)
     }
 
-    fn visit_mut_switch(&mut self, expr: &mut Expr, values: &mut [Expr], results: &mut [Expr]) {
+    fn visit_mut_switch(
+        &mut self,
+        expr: &mut Expr,
+        values: &mut [Rc<Expr>],
+        results: &mut [Rc<Expr>],
+    ) {
         self.super_mut_switch(expr, values, results)
     }
 
-    fn visit_mut_tuple(&mut self, fields: &mut [Expr]) {
+    fn visit_mut_tuple(&mut self, fields: &mut [Rc<Expr>]) {
         self.super_mut_tuple(fields)
     }
 
@@ -36,23 +43,23 @@ pub trait VisitorMut {
     fn super_mut_expr(&mut self, expr: &mut Expr) {
         match expr {
             Expr::Value(e) => self.visit_mut_value(e),
-            Expr::Apply(e1, e2) => self.visit_mut_apply(e1, e2),
-            Expr::BinaryOp(op, e1, e2) => self.visit_mut_binary_op(op, e1, e2),
-            Expr::Switch(e1, e2, e3) => self.visit_mut_switch(e1, e2, e3),
+            Expr::Apply(e1, e2) => self.visit_mut_apply(Rc::make_mut(e1), e2),
+            Expr::BinaryOp(op, e1, e2) => {
+                self.visit_mut_binary_op(op, Rc::make_mut(e1), Rc::make_mut(e2))
+            }
+            Expr::Switch(e1, e2, e3) => self.visit_mut_switch(Rc::make_mut(e1), e2, e3),
             Expr::Tuple(e1) => self.visit_mut_tuple(e1),
-            Expr::Projection(e1, index) => self.visit_mut_projection(e1, *index),
-            Expr::Assert(e1, e2) => self.visit_mut_assert(e1, e2),
-            Expr::Uninitialized => (),
+            Expr::Projection(e1, index) => self.visit_mut_projection(Rc::make_mut(e1), *index),
+            Expr::Assert(e1, e2) => self.visit_mut_assert(Rc::make_mut(e1), Rc::make_mut(e2)),
+            Expr::Abort(_) | Expr::Uninitialized => (),
         }
     }
-    fn super_mut_value(&mut self, _: &mut Value) {
-        ()
-    }
+    fn super_mut_value(&mut self, _: &mut Value) {}
 
-    fn super_mut_apply(&mut self, func: &mut Expr, args: &mut [Expr]) {
+    fn super_mut_apply(&mut self, func: &mut Expr, args: &mut [Rc<Expr>]) {
         self.visit_mut_expr(func);
         for arg in args {
-            self.visit_mut_expr(arg);
+            self.visit_mut_expr(Rc::make_mut(arg));
         }
     }
 
@@ -61,19 +68,24 @@ pub trait VisitorMut {
         self.visit_mut_expr(e2);
     }
 
-    fn super_mut_switch(&mut self, expr: &mut Expr, values: &mut [Expr], results: &mut [Expr]) {
+    fn super_mut_switch(
+        &mut self,
+        expr: &mut Expr,
+        values: &mut [Rc<Expr>],
+        results: &mut [Rc<Expr>],
+    ) {
         self.visit_mut_expr(expr);
         for value in values {
-            self.visit_mut_expr(value);
+            self.visit_mut_expr(Rc::make_mut(value));
         }
         for result in results {
-            self.visit_mut_expr(result);
+            self.visit_mut_expr(Rc::make_mut(result));
         }
     }
 
-    fn super_mut_tuple(&mut self, fields: &mut [Expr]) {
+    fn super_mut_tuple(&mut self, fields: &mut [Rc<Expr>]) {
         for field in fields {
-            self.visit_mut_expr(field);
+            self.visit_mut_expr(Rc::make_mut(field));
         }
     }
 
@@ -0,0 +1,40 @@
+use std::rc::Rc;
+
+use sire_sir::*;
+
+#[test]
+fn test_hash_consing() {
+    let mut arena = ExprArena::default();
+    let x = arena.intern(Node::Value(Value::Arg(1, Ty::Uint(8))));
+    let y = arena.intern(Node::Value(Value::Arg(2, Ty::Uint(8))));
+
+    // `(x + y) * (x + y)` stores the sum once.
+    let sum = arena.intern(Node::BinaryOp(BinOp::Add, x, y));
+    let product = arena.intern(Node::BinaryOp(BinOp::Mul, sum, sum));
+    assert_eq!(arena.intern(Node::BinaryOp(BinOp::Add, x, y)), sum);
+    assert_eq!(arena.len(), 4);
+    assert_eq!(arena.shared(product), vec![sum]);
+
+    let expr = arena.expr(product);
+    assert_eq!(arena.insert(&expr), product);
+}
+
+#[test]
+fn test_shared_expr() {
+    // `x = x + x` 64 times, which is a tree with 2^64 leaves.
+    let mut arena = ExprArena::default();
+    let mut id = arena.intern(Node::Value(Value::Arg(1, Ty::Uint(8))));
+    for _ in 0..64 {
+        id = arena.intern(Node::BinaryOp(BinOp::Add, id, id));
+    }
+
+    let expr = arena.expr(id);
+    match &expr {
+        Expr::BinaryOp(_, e1, e2) => assert!(Rc::ptr_eq(e1, e2)),
+        _ => unreachable!(),
+    }
+    assert_eq!(arena.insert(&expr), id);
+    let mut copy = ExprArena::default();
+    copy.insert(&expr);
+    assert_eq!(copy.len(), 65);
+}
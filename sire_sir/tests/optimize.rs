@@ -0,0 +1,107 @@
+use std::rc::Rc;
+
+use proptest::prelude::*;
+
+use sire_sir::*;
+
+fn body(code: &str) -> Expr {
+    parse_func_defs(&format!("(defun f[] ((int 8) (int 8) (uint 8) bool) {})", code)).unwrap()[0]
+        .body
+        .clone()
+}
+
+fn optimized(code: &str) -> Expr {
+    let mut expr = body(code);
+    expr.optimize();
+    expr
+}
+
+fn fold(bin_op: BinOp, a: u128, b: u128, ty: Ty) -> Expr {
+    let mut expr = Expr::BinaryOp(
+        bin_op,
+        Rc::new(Expr::Value(Value::Const(a, ty.clone()))),
+        Rc::new(Expr::Value(Value::Const(b, ty))),
+    );
+    expr.optimize();
+    expr
+}
+
+#[test]
+fn test_simplify() {
+    assert_eq!(optimized("(+ _1 (const (int 8) 0))"), body("_1"));
+    assert_eq!(optimized("(* (const (int 8) 1) _1)"), body("_1"));
+    assert_eq!(optimized("(- (+ _1 _1) (+ _1 _1))"), body("(const (int 8) 0)"));
+    assert_eq!(optimized("(< (const (int 8) 3) _1)"), body("(> _1 (const (int 8) 3))"));
+    assert_eq!(optimized("(= (< _1 _1) (const bool 0))"), body("(const bool 1)"));
+    assert_eq!(optimized("(= _3 (const bool 1))"), body("_3"));
+    assert_eq!(
+        optimized("(switch (const bool 0) ((const bool 0) -> (const (int 8) 0)) (else -> _1))"),
+        body("(const (int 8) 0)")
+    );
+    assert_eq!(
+        optimized("(switch _3 ((const bool 0) -> (- _1 _1)) (else -> (const (int 8) 0)))"),
+        body("(const (int 8) 0)")
+    );
+    assert_eq!(optimized("(proj (tuple _1 _2) 1)"), body("_2"));
+    // Divisions by zero are kept.
+    assert_eq!(optimized("(/ _1 (const (int 8) 0))"), body("(/ _1 (const (int 8) 0))"));
+}
+
+/// Doubles `_1` `depth` times, adding zero to the operand at every step if `zero` is set.
+fn doubling(depth: usize, zero: bool) -> Expr {
+    let mut expr = Expr::Value(Value::Arg(1, Ty::Uint(8)));
+    for _ in 0..depth {
+        if zero {
+            let zero = Expr::Value(Value::Const(0, Ty::Uint(8)));
+            expr = Expr::BinaryOp(BinOp::Add, Rc::new(expr), Rc::new(zero));
+        }
+        let operand = Rc::new(expr);
+        expr = Expr::BinaryOp(BinOp::Add, operand.clone(), operand);
+    }
+    expr
+}
+
+#[test]
+fn test_simplify_shared() {
+    // The trees have 2^64 leaves, so they can only be simplified if shared subexpressions are
+    // simplified once. They are compared through an arena for the same reason.
+    let mut expr = doubling(64, true);
+    expr.optimize();
+    let mut arena = ExprArena::default();
+    assert_eq!(arena.insert(&expr), arena.insert(&doubling(64, false)));
+    assert_eq!(arena.len(), 65);
+
+    let operand = Rc::new(doubling(64, false));
+    let mut expr = Expr::BinaryOp(BinOp::Sub, operand.clone(), operand);
+    expr.optimize();
+    assert_eq!(expr, Expr::Value(Value::Const(0, Ty::Uint(8))));
+}
+
+proptest! {
+    #[test]
+    fn test_fold_uint(a: u8, b: u8) {
+        let expected = |value: u8| Expr::Value(Value::Const(value as u128, Ty::Uint(8)));
+        prop_assert_eq!(fold(BinOp::Add, a as u128, b as u128, Ty::Uint(8)), expected(a.wrapping_add(b)));
+        prop_assert_eq!(fold(BinOp::Sub, a as u128, b as u128, Ty::Uint(8)), expected(a.wrapping_sub(b)));
+        prop_assert_eq!(fold(BinOp::Mul, a as u128, b as u128, Ty::Uint(8)), expected(a.wrapping_mul(b)));
+        if b != 0 {
+            prop_assert_eq!(fold(BinOp::Div, a as u128, b as u128, Ty::Uint(8)), expected(a / b));
+        }
+    }
+
+    #[test]
+    fn test_fold_int(a: i8, b: i8) {
+        let expected = |value: i8| Expr::Value(Value::Const(value as u8 as u128, Ty::Int(8)));
+        let (x, y) = (a as u8 as u128, b as u8 as u128);
+        prop_assert_eq!(fold(BinOp::Sub, x, y, Ty::Int(8)), expected(a.wrapping_sub(b)));
+        prop_assert_eq!(fold(BinOp::Mul, x, y, Ty::Int(8)), expected(a.wrapping_mul(b)));
+        if b != 0 {
+            prop_assert_eq!(fold(BinOp::Div, x, y, Ty::Int(8)), expected(a.wrapping_div(b)));
+            prop_assert_eq!(fold(BinOp::Rem, x, y, Ty::Int(8)), expected(a.wrapping_rem(b)));
+        }
+        prop_assert_eq!(
+            fold(BinOp::Lt, x, y, Ty::Int(8)),
+            Expr::Value(Value::Const((a < b) as u128, Ty::Bool))
+        );
+    }
+}
@@ -0,0 +1,171 @@
+use std::rc::Rc;
+
+use proptest::collection::vec;
+use proptest::prelude::*;
+
+use sire_sir::*;
+
+fn helper() -> FuncDef {
+    FuncDef {
+        id: FuncId("helper".to_owned()),
+        body: Expr::Value(Value::Arg(1, Ty::Uint(64))),
+        ty: Ty::Func(vec![Ty::Uint(64), Ty::Uint(64)], vec![]),
+    }
+}
+
+fn arb_ty() -> impl Strategy<Value = Ty> {
+    let leaf = prop_oneof![
+        Just(Ty::Bool),
+        (1usize..=128).prop_map(Ty::Int),
+        (1usize..=128).prop_map(Ty::Uint),
+    ];
+    leaf.prop_recursive(2, 8, 3, |inner| vec(inner, 0..3).prop_map(Ty::Tuple))
+}
+
+fn arb_bin_op() -> impl Strategy<Value = BinOp> {
+    prop_oneof![
+        Just(BinOp::Add),
+        Just(BinOp::Sub),
+        Just(BinOp::Mul),
+        Just(BinOp::Div),
+        Just(BinOp::Rem),
+        Just(BinOp::BitXor),
+        Just(BinOp::BitAnd),
+        Just(BinOp::BitOr),
+        Just(BinOp::Shl),
+        Just(BinOp::Shr),
+        Just(BinOp::Eq),
+        Just(BinOp::Lt),
+        Just(BinOp::Le),
+        Just(BinOp::Ne),
+        Just(BinOp::Ge),
+        Just(BinOp::Gt),
+    ]
+}
+
+fn shared(exprs: Vec<Expr>) -> Vec<Rc<Expr>> {
+    exprs.into_iter().map(Rc::new).collect()
+}
+
+fn arb_expr() -> impl Strategy<Value = Expr> {
+    let leaf = prop_oneof![
+        Just(Expr::Value(Value::Arg(1, Ty::Uint(64)))),
+        Just(Expr::Value(Value::Arg(2, Ty::Bool))),
+        Just(Expr::Value(Value::ConstParam(Param(0, Ty::Uint(64))))),
+        Just(Expr::Value(Value::Function(helper().id, helper().ty))),
+        Just(Expr::Uninitialized),
+        arb_ty().prop_map(Expr::Abort),
+        (any::<u128>(), arb_ty()).prop_map(|(n, ty)| Expr::Value(Value::Const(n, ty))),
+    ];
+    leaf.prop_recursive(4, 64, 4, |inner| {
+        prop_oneof![
+            (inner.clone(), vec(inner.clone(), 0..4))
+                .prop_map(|(func, args)| Expr::Apply(Rc::new(func), shared(args))),
+            (arb_bin_op(), inner.clone(), inner.clone()).prop_map(|(op, e1, e2)| Expr::BinaryOp(
+                op,
+                Rc::new(e1),
+                Rc::new(e2)
+            )),
+            (inner.clone(), vec((inner.clone(), inner.clone()), 0..3), inner.clone()).prop_map(
+                |(discr, branches, otherwise)| {
+                    let (values, mut targets): (Vec<_>, Vec<_>) = branches.into_iter().unzip();
+                    targets.push(otherwise);
+                    Expr::Switch(Rc::new(discr), shared(values), shared(targets))
+                }
+            ),
+            vec(inner.clone(), 0..4).prop_map(|fields| Expr::Tuple(shared(fields))),
+            (inner.clone(), 0usize..4).prop_map(|(tuple, i)| Expr::Projection(Rc::new(tuple), i)),
+            (inner.clone(), inner)
+                .prop_map(|(cond, result)| Expr::Assert(Rc::new(cond), Rc::new(result))),
+        ]
+    })
+}
+
+proptest! {
+    #[test]
+    fn test_display_parse_roundtrip(body in arb_expr()) {
+        let func = FuncDef {
+            id: FuncId("f".to_owned()),
+            body,
+            ty: Ty::Func(vec![Ty::Uint(64), Ty::Uint(64), Ty::Bool], vec![Param(0, Ty::Uint(64))]),
+        };
+        let funcs = vec![func, helper()];
+
+        let text = funcs.iter().map(|func| func.to_string()).collect::<Vec<_>>().join("\n");
+
+        prop_assert_eq!(parse_func_defs(&text), Ok(funcs));
+    }
+}
+
+#[test]
+fn test_parse_names() {
+    let funcs = parse_func_defs(
+        "(defun sum[] ((uint 64) (uint 64) (uint 64))
+            (switch (> _2 (const (uint 64) 0))
+                ((const bool 0) -> _1)
+                (else -> (sum (+ _1 (const (uint 64) 1)) (- _2 (const (uint 64) 1))))))",
+    )
+    .unwrap();
+
+    let ty = Ty::Func(vec![Ty::Uint(64), Ty::Uint(64), Ty::Uint(64)], vec![]);
+    let x1 = Expr::Value(Value::Arg(1, Ty::Uint(64)));
+    let x2 = Expr::Value(Value::Arg(2, Ty::Uint(64)));
+    let one = Expr::Value(Value::Const(1, Ty::Uint(64)));
+
+    assert_eq!(funcs.len(), 1);
+    assert_eq!(funcs[0].ty, ty);
+    assert!(funcs[0].is_recursive());
+    assert_eq!(
+        funcs[0].body,
+        Expr::Switch(
+            Rc::new(Expr::BinaryOp(
+                BinOp::Gt,
+                Rc::new(x2.clone()),
+                Rc::new(Expr::Value(Value::Const(0, Ty::Uint(64)))),
+            )),
+            vec![Rc::new(Expr::Value(Value::Const(0, Ty::Bool)))],
+            vec![
+                Rc::new(x1.clone()),
+                Rc::new(Expr::Apply(
+                    Rc::new(Expr::Value(Value::Function(funcs[0].id.clone(), ty))),
+                    vec![
+                        Rc::new(Expr::BinaryOp(BinOp::Add, Rc::new(x1), Rc::new(one.clone()))),
+                        Rc::new(Expr::BinaryOp(BinOp::Sub, Rc::new(x2), Rc::new(one))),
+                    ],
+                )),
+            ],
+        )
+    );
+}
+
+#[test]
+fn test_roundtrip_reserved_name() {
+    let mut symbols = SymbolTable::default();
+    let abort = symbols.insert("abort");
+    let ty = Ty::Func(vec![Ty::Uint(8), Ty::Uint(8)], vec![]);
+    let funcs = vec![
+        FuncDef {
+            id: abort.clone(),
+            body: Expr::Value(Value::Arg(1, Ty::Uint(8))),
+            ty: ty.clone(),
+        },
+        FuncDef {
+            id: FuncId("f".to_owned()),
+            body: Expr::Apply(
+                Rc::new(Expr::Value(Value::Function(abort.clone(), ty.clone()))),
+                vec![Rc::new(Expr::Value(Value::Arg(1, Ty::Uint(8))))],
+            ),
+            ty,
+        },
+    ];
+
+    let text = funcs.iter().map(|func| func.to_string()).collect::<Vec<_>>().join("\n");
+
+    assert_eq!(abort, FuncId("abort!1".to_owned()));
+    assert_eq!(parse_func_defs(&text), Ok(funcs));
+}
+
+#[test]
+fn test_parse_undefined_function() {
+    assert!(parse_func_defs("(defun f[] ((uint 8)) (g))").is_err());
+}
@@ -0,0 +1,82 @@
+use sire_sir::*;
+
+const EVEN_ODD: &str = "
+    (defun main[] ((uint 8)) (const (uint 8) 0))
+    (defun is_even[] (bool (uint 8))
+        (switch (= _1 (const (uint 8) 0))
+            ((const bool 1) -> (const bool 1))
+            (else -> (is_odd (- _1 (const (uint 8) 1))))))
+    (defun is_odd[] (bool (uint 8))
+        (switch (= _1 (const (uint 8) 0))
+            ((const bool 1) -> (const bool 0))
+            (else -> (is_even (- _1 (const (uint 8) 1))))))
+    (defun check[] (bool (uint 8)) (is_even _1))";
+
+fn ids(funcs: &[&FuncDef]) -> Vec<String> {
+    funcs.iter().map(|func| func.id.to_string()).collect()
+}
+
+fn id(name: &str) -> FuncId {
+    FuncId(name.to_owned())
+}
+
+#[test]
+fn test_call_graph() {
+    let program = Program::new(parse_func_defs(EVEN_ODD).unwrap(), SymbolTable::default());
+
+    let components = program.components().iter().map(|c| ids(c)).collect::<Vec<_>>();
+    assert_eq!(components, vec![vec!["main"], vec!["is_even", "is_odd"], vec!["check"]]);
+    assert_eq!(ids(&program.topological_order()), vec!["main", "is_even", "is_odd", "check"]);
+
+    assert_eq!(ids(&program.callees(&id("check"))), vec!["is_even"]);
+    assert_eq!(ids(&program.callers(&id("is_even"))), vec!["is_odd", "check"]);
+    assert_eq!(ids(&program.reachable(&[&id("check")])), vec!["is_even", "is_odd", "check"]);
+    assert!(program.undefined_callees(&id("check")).is_empty());
+
+    assert!(program.is_recursive(&id("is_odd")));
+    assert!(!program.is_recursive(&id("check")));
+    assert!(program.funcs().iter().all(|func| !func.is_recursive()));
+}
+
+#[test]
+fn test_inline() {
+    let program = Program::new(
+        parse_func_defs(&format!(
+            "{}
+            (defun double[] ((uint 8) (uint 8)) (+ _1 _1))
+            (defun quad[] ((uint 8) (uint 8)) (double (double _1)))
+            (defun swap_add[] ((uint 8) (uint 8) (uint 8)) (sub _2 _1))
+            (defun sub[] ((uint 8) (uint 8) (uint 8)) (- _1 (double _2)))",
+            EVEN_ODD
+        ))
+        .unwrap(),
+        SymbolTable::default(),
+    );
+    let expected = parse_func_defs(
+        "(defun quad[] ((uint 8) (uint 8)) (+ (+ _1 _1) (+ _1 _1)))
+        (defun swap_add[] ((uint 8) (uint 8) (uint 8)) (- _2 (+ _1 _1)))",
+    )
+    .unwrap();
+
+    let inlined = program.inline();
+
+    assert_eq!(inlined.get(&id("quad")).unwrap().body, expected[0].body);
+    assert_eq!(inlined.get(&id("swap_add")).unwrap().body, expected[1].body);
+    // Recursive functions are kept as calls.
+    assert_eq!(inlined.get(&id("check")), program.get(&id("check")));
+}
+
+#[test]
+fn test_call() {
+    let program = Program::new(parse_func_defs(EVEN_ODD).unwrap(), SymbolTable::default());
+    let input = |value| vec![Value::Const(value, Ty::Uint(8))];
+
+    assert_eq!(program.call(&id("check"), &input(10)), Some(Value::Const(1, Ty::Bool)));
+    assert_eq!(program.call(&id("is_odd"), &input(10)), Some(Value::Const(0, Ty::Bool)));
+    assert_eq!(program.call(&id("check"), &input(255)), Some(Value::Const(0, Ty::Bool)));
+    assert_eq!(program.call(&id("check"), &[]), None);
+
+    let arms = program.get(&id("is_even")).unwrap().body.arms();
+    assert_eq!(arms.len(), 2);
+    assert_eq!(arms[1][0].to_string(), "(!= (= _1 (const (uint 8) 0)) (const bool 1))");
+}
@@ -0,0 +1,29 @@
+use sire_sir::*;
+
+#[test]
+fn test_symbol_names() {
+    let mut symbols = SymbolTable::default();
+
+    let sum = symbols.insert("utils::sum");
+    let cmp = symbols.insert("<Point as std::cmp::PartialEq>::eq");
+    let param = symbols.insert("p0");
+
+    assert_eq!(sum, FuncId("utils.sum".to_owned()));
+    assert_eq!(cmp, FuncId("<Point_as_std.cmp.PartialEq>.eq".to_owned()));
+    assert_eq!(param, FuncId("p0!1".to_owned()));
+    assert_eq!(symbols.insert("utils::sum"), sum);
+    assert_eq!(symbols.path(&cmp), Some("<Point as std::cmp::PartialEq>::eq"));
+
+    // Different paths that sanitize to the same name still get different identifiers.
+    assert_eq!(symbols.insert("utils.sum"), FuncId("utils.sum!1".to_owned()));
+}
+
+#[test]
+fn test_parse_sanitized_names() {
+    let mut symbols = SymbolTable::default();
+    let id = symbols.insert("utils::id");
+
+    let funcs = parse_func_defs(&format!("(defun {}[] ((uint 8) (uint 8)) _1)", id)).unwrap();
+
+    assert_eq!(funcs[0].id, id);
+}
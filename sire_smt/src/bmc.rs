@@ -0,0 +1,364 @@
+//! Bounded model checking.
+//!
+//! Recursive functions are unrolled up to a fixed depth: the copy `f@i` of a recursive function
+//! `f` calls the copies at level `i - 1` of the functions in its component, and `f@0` is left
+//! undefined. Every function also gets two flags: `f@exceeded`, which is true if evaluating the
+//! function reaches a copy at level `0`, and `f@failed`, which is true if an assertion fails.
+//! Queries assume that the bound is not exceeded, so they are quantifier free and their results
+//! hold for every input whose evaluation fits in the bound.
+//!
+//! The `f@failed` flags are also used without unrolling, defined next to the functions
+//! themselves, so the unbounded checks can tell whether a function fails.
+
+use std::rc::Rc;
+
+use sire_sir::*;
+
+use crate::smtlib::{datatype_declarations, ToSmtlib};
+use crate::{apply, declare_inputs, same_outcome, CheckResult, Solver};
+
+/// The outcome of a bounded check.
+#[derive(Debug, PartialEq, Eq)]
+pub enum BoundedResult {
+    /// The property holds for every input that does not exceed the bound.
+    Verified,
+    /// The property does not hold for some input that does not exceed the bound.
+    Falsified,
+    Undecided,
+    Unknown(String),
+}
+
+impl From<CheckResult> for BoundedResult {
+    /// Queries assert the negation of the property, so a model is a counterexample.
+    fn from(result: CheckResult) -> Self {
+        match result {
+            CheckResult::Sat => BoundedResult::Falsified,
+            CheckResult::Unsat => BoundedResult::Verified,
+            CheckResult::Undecided => BoundedResult::Undecided,
+            CheckResult::Unknown(output) => BoundedResult::Unknown(output),
+        }
+    }
+}
+
+/// Returns the SMT-LIB definitions of the functions of the program with the recursive ones
+/// unrolled up to `depth`.
+pub fn unroll(program: &Program, depth: usize) -> String {
+    Unrolling { program, depth }.definitions().join("\n")
+}
+
+/// Checks whether two functions fail for the same arguments and return the same value for the
+/// others, for every argument whose evaluation does not exceed `depth` nested recursive calls.
+pub fn check_equality(
+    program: &Program,
+    a: &FuncId,
+    b: &FuncId,
+    depth: usize,
+    solver: Solver,
+) -> Result<BoundedResult, Box<dyn std::error::Error>> {
+    let program = program.restrict(&[a, b]);
+    let unrolling = Unrolling { program: &program, depth };
+    let (a, b) = (crate::get(&program, a)?, crate::get(&program, b)?);
+    if a.ty != b.ty {
+        return Ok(BoundedResult::Falsified);
+    }
+
+    let (decls, inputs) = declare_inputs(&a.ty);
+    let (a, b) = (unrolling.entry(&a.id), unrolling.entry(&b.id));
+    let outcome = |id: &FuncId| (Some(apply(&Flag::Failed.id(id), &inputs)), apply(id, &inputs));
+    let mut code = unrolling.definitions();
+    code.extend(decls);
+    code.extend(vec![
+        format!(
+            "(assert (not (or {} {})))",
+            apply(&Flag::Exceeded.id(&a), &inputs),
+            apply(&Flag::Exceeded.id(&b), &inputs)
+        ),
+        format!("(assert (not {}))", same_outcome(outcome(&a), outcome(&b))),
+        "(check-sat)".to_owned(),
+    ]);
+    solver.call(&code.join("\n")).map(|result| CheckResult::from_string(result).into())
+}
+
+/// Checks that no assertion fails while evaluating the function with any argument whose
+/// evaluation does not exceed `depth` nested recursive calls.
+pub fn check_assertions(
+    program: &Program,
+    id: &FuncId,
+    depth: usize,
+    solver: Solver,
+) -> Result<BoundedResult, Box<dyn std::error::Error>> {
+    let program = program.restrict(&[id]);
+    let unrolling = Unrolling { program: &program, depth };
+    let func = crate::get(&program, id)?;
+
+    let (decls, inputs) = declare_inputs(&func.ty);
+    let mut code = unrolling.definitions();
+    code.extend(decls);
+    let entry = unrolling.entry(&func.id);
+    code.extend(vec![
+        format!("(assert (not {}))", apply(&Flag::Exceeded.id(&entry), &inputs)),
+        format!("(assert {})", apply(&Flag::Failed.id(&entry), &inputs)),
+        "(check-sat)".to_owned(),
+    ]);
+    solver.call(&code.join("\n")).map(|result| CheckResult::from_string(result).into())
+}
+
+#[derive(Clone, Copy, PartialEq, Eq)]
+enum Flag {
+    Exceeded,
+    Failed,
+}
+
+impl Flag {
+    fn id(self, func: &FuncId) -> FuncId {
+        match self {
+            Flag::Exceeded => FuncId(format!("{}@exceeded", func)),
+            Flag::Failed => FuncId(format!("{}@failed", func)),
+        }
+    }
+}
+
+/// The flag of a function of the program that is true if its evaluation fails.
+pub(crate) fn failed_id(func: &FuncId) -> FuncId {
+    Flag::Failed.id(func)
+}
+
+/// Whether evaluating the function can fail, because it or a function it calls contains an
+/// `Abort` or an `Assert`.
+pub(crate) fn can_fail(program: &Program, id: &FuncId) -> bool {
+    let mut arena = ExprArena::default();
+    for func in program.reachable(&[id]) {
+        arena.insert(&func.body);
+    }
+    for node in arena.nodes() {
+        if let Node::Abort(_) | Node::Assert(..) = node {
+            return true;
+        }
+    }
+    false
+}
+
+/// Returns a boolean expression over the arguments of the function that is true if evaluating
+/// one of the expressions fails, or `None` if none of them can. Recursive functions are not
+/// unrolled: the functions of the program that can fail are checked through their own flags.
+pub(crate) fn failure(program: &Program, exprs: &[Expr]) -> Option<Expr> {
+    let resolve = |callee: &FuncId| {
+        if can_fail(program, callee) {
+            Some((callee.clone(), false))
+        } else {
+            None
+        }
+    };
+    let failure = or(exprs.iter().map(|expr| flag_expr(expr, Flag::Failed, &resolve)).collect());
+    if failure == bool_const(false) {
+        None
+    } else {
+        Some(failure)
+    }
+}
+
+/// Defines the `f@failed` flag of the function, if it can fail.
+pub(crate) fn failed_flag(program: &Program, func: &FuncDef) -> Option<FuncDef> {
+    if !can_fail(program, &func.id) {
+        return None;
+    }
+    let body = failure(program, std::slice::from_ref(&func.body))?;
+    Some(FuncDef { id: failed_id(&func.id), body, ty: flag_ty(&func.ty) })
+}
+
+fn level_id(func: &FuncId, level: usize) -> FuncId {
+    FuncId(format!("{}@{}", func, level))
+}
+
+/// The type of the flags of a function with the given type.
+fn flag_ty(ty: &Ty) -> Ty {
+    match ty {
+        Ty::Func(args, params) => {
+            let args = Some(Ty::Bool).into_iter().chain(args.iter().skip(1).cloned()).collect();
+            Ty::Func(args, params.clone())
+        }
+        _ => unreachable!(),
+    }
+}
+
+fn bool_const(value: bool) -> Expr {
+    Expr::Value(Value::Const(value as u128, Ty::Bool))
+}
+
+fn not(expr: Expr) -> Expr {
+    let (true_expr, false_expr) = (Rc::new(bool_const(true)), Rc::new(bool_const(false)));
+    Expr::Switch(Rc::new(expr), vec![false_expr.clone()], vec![true_expr, false_expr])
+}
+
+fn or(exprs: Vec<Expr>) -> Expr {
+    let exprs = exprs.into_iter().filter(|expr| *expr != bool_const(false)).collect::<Vec<_>>();
+    if exprs.contains(&bool_const(true)) {
+        return bool_const(true);
+    }
+    exprs
+        .into_iter()
+        .rev()
+        .fold(None, |rest, expr| match rest {
+            Some(rest) => Some(Expr::Switch(
+                Rc::new(expr),
+                vec![Rc::new(bool_const(false))],
+                vec![Rc::new(rest), Rc::new(bool_const(true))],
+            )),
+            None => Some(expr),
+        })
+        .unwrap_or_else(|| bool_const(false))
+}
+
+struct Unrolling<'a> {
+    program: &'a Program,
+    depth: usize,
+}
+
+impl<'a> Unrolling<'a> {
+    /// The copy of the function called from outside of its component.
+    fn entry(&self, id: &FuncId) -> FuncId {
+        if self.program.is_recursive(id) {
+            level_id(id, self.depth)
+        } else {
+            id.clone()
+        }
+    }
+
+    /// Returns the copy of `callee` called by the copy at `level` of a function in `component`
+    /// and whether that copy is at level `0`. Functions outside the program are not renamed.
+    fn resolve(
+        &self,
+        component: &[&FuncDef],
+        level: usize,
+        callee: &FuncId,
+    ) -> Option<(FuncId, bool)> {
+        self.program.get(callee)?;
+        if self.program.is_recursive(callee) && component.iter().any(|func| func.id == *callee) {
+            Some((level_id(callee, level - 1), level == 1))
+        } else {
+            Some((self.entry(callee), false))
+        }
+    }
+
+    fn definitions(&self) -> Vec<String> {
+        let mut code = datatype_declarations(self.program);
+        for component in self.program.components() {
+            code.extend(component.iter().map(|func| format!("; {}", self.program.name(&func.id))));
+            if self.program.is_recursive(&component[0].id) {
+                code.extend(component.iter().map(|func| declare(func, &level_id(&func.id, 0))));
+                for level in 1..=self.depth {
+                    for func in &component {
+                        code.extend(self.define(&component, func, level));
+                    }
+                }
+            } else {
+                code.extend(self.define(&component, component[0], self.depth));
+            }
+        }
+        code
+    }
+
+    /// Defines the copy of the function at the given level, or the function itself if it is not
+    /// recursive, together with its flags.
+    fn define(&self, component: &[&FuncDef], func: &FuncDef, level: usize) -> Vec<String> {
+        let resolve = |callee: &FuncId| self.resolve(component, level, callee);
+        let id = if self.program.is_recursive(&func.id) {
+            level_id(&func.id, level)
+        } else {
+            func.id.clone()
+        };
+
+        let mut code = vec![FuncDef {
+            id: id.clone(),
+            body: rename(&func.body, &resolve),
+            ty: func.ty.clone(),
+        }
+        .to_smtlib()];
+        for &flag in &[Flag::Exceeded, Flag::Failed] {
+            let body = flag_expr(&func.body, flag, &resolve);
+            code.push(FuncDef { id: flag.id(&id), body, ty: flag_ty(&func.ty) }.to_smtlib());
+        }
+        code
+    }
+}
+
+/// Declares a function with the type of `func` and the given name, leaving it uninterpreted.
+pub(crate) fn declare(func: &FuncDef, id: &FuncId) -> String {
+    let (args_ty, params) = match &func.ty {
+        Ty::Func(args_ty, params) => (args_ty, params),
+        _ => unreachable!(),
+    };
+    let inputs = args_ty
+        .iter()
+        .skip(1)
+        .chain(params.iter().map(|Param(_, ty)| ty))
+        .map(ToSmtlib::to_smtlib)
+        .collect::<Vec<_>>()
+        .join(" ");
+    format!("(declare-fun {} ({}) {})", id.to_smtlib(), inputs, args_ty[0].to_smtlib())
+}
+
+/// Renames the functions called in `expr` to the copies returned by `resolve`.
+fn rename(expr: &Expr, resolve: &dyn Fn(&FuncId) -> Option<(FuncId, bool)>) -> Expr {
+    struct Renamer<'a> {
+        resolve: &'a dyn Fn(&FuncId) -> Option<(FuncId, bool)>,
+    }
+
+    impl<'a> VisitorMut for Renamer<'a> {
+        fn visit_mut_value(&mut self, value: &mut Value) {
+            if let Value::Function(id, _) = value {
+                if let Some((copy, _)) = (self.resolve)(id) {
+                    *id = copy;
+                }
+            }
+        }
+    }
+
+    let mut expr = expr.clone();
+    Renamer { resolve }.visit_mut_expr(&mut expr);
+    expr
+}
+
+/// Returns a boolean expression over the arguments of the function that is true if the given
+/// flag is raised while evaluating `expr`.
+fn flag_expr(expr: &Expr, flag: Flag, resolve: &dyn Fn(&FuncId) -> Option<(FuncId, bool)>) -> Expr {
+    let flag_of = |expr: &Expr| flag_expr(expr, flag, resolve);
+    match expr {
+        Expr::Value(_) | Expr::Uninitialized => bool_const(false),
+        Expr::Abort(_) => bool_const(flag == Flag::Failed),
+        Expr::BinaryOp(_, e1, e2) => or(vec![flag_of(e1), flag_of(e2)]),
+        Expr::Tuple(fields) => or(fields.iter().map(|field| flag_of(field)).collect()),
+        Expr::Projection(tuple, _) => flag_of(tuple),
+        Expr::Assert(condition, result) => {
+            let mut flags = vec![flag_of(condition), flag_of(result)];
+            if flag == Flag::Failed {
+                flags.push(not(rename(condition, resolve)));
+            }
+            or(flags)
+        }
+        Expr::Switch(discr, values, results) => {
+            let results = results.iter().map(|result| Rc::new(flag_of(result))).collect::<Vec<_>>();
+            let branches = if results.iter().all(|result| **result == bool_const(false)) {
+                bool_const(false)
+            } else {
+                Expr::Switch(Rc::new(rename(discr, resolve)), values.clone(), results)
+            };
+            or(vec![flag_of(discr), branches])
+        }
+        Expr::Apply(func, args) => {
+            let mut flags = args.iter().map(|arg| flag_of(arg)).collect::<Vec<_>>();
+            flags.push(flag_of(func));
+            if let Expr::Value(Value::Function(id, ty)) = func.as_ref() {
+                match resolve(id) {
+                    Some((_, true)) => flags.push(bool_const(flag == Flag::Exceeded)),
+                    Some((copy, false)) => flags.push(Expr::Apply(
+                        Rc::new(Expr::Value(Value::Function(flag.id(&copy), flag_ty(ty)))),
+                        args.iter().map(|arg| Rc::new(rename(arg, resolve))).collect(),
+                    )),
+                    None => (),
+                }
+            }
+            or(flags)
+        }
+    }
+}
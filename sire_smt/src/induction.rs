@@ -0,0 +1,169 @@
+//! Equivalence proofs by induction over the recursion of a function.
+//!
+//! To prove that a recursive function `f` is equal to `g`, the body of `f` is split into its
+//! paths. A path without recursive calls is a base case, where the path must fail exactly when `g`
+//! applied to the same arguments fails, and give the same result otherwise. In the other paths,
+//! every recursive call to `f` is replaced by a call to `g` following the induction hypothesis,
+//! and again the path must behave like `g`. Every obligation is quantifier free and is discharged
+//! on its own. The scheme follows the recursion of `f`, so it is only sound if `f` terminates.
+
+use std::fmt;
+
+use sire_sir::*;
+
+use crate::smtlib::ToSmtlib;
+use crate::{apply, bmc, declare_inputs, same_outcome, CheckResult, Solver};
+
+#[derive(Clone, Copy, Debug, PartialEq, Eq)]
+pub enum ObligationKind {
+    Base,
+    Step,
+}
+
+/// The outcome of discharging an obligation.
+#[derive(Debug, PartialEq, Eq)]
+pub enum Outcome {
+    Holds,
+    Fails,
+    Undecided,
+    Unknown(String),
+}
+
+impl From<CheckResult> for Outcome {
+    /// Obligations assert the negation of what must hold, so a model is a counterexample.
+    fn from(result: CheckResult) -> Self {
+        match result {
+            CheckResult::Sat => Outcome::Fails,
+            CheckResult::Unsat => Outcome::Holds,
+            CheckResult::Undecided => Outcome::Undecided,
+            CheckResult::Unknown(output) => Outcome::Unknown(output),
+        }
+    }
+}
+
+/// The proof obligation of a single path of the function used for the induction.
+pub struct Obligation {
+    pub kind: ObligationKind,
+    /// The conditions of the path, with the induction hypothesis applied.
+    pub conditions: Vec<Expr>,
+    code: String,
+}
+
+impl Obligation {
+    /// The SMT-LIB query, which is unsatisfiable if the obligation holds.
+    pub fn code(&self) -> &str {
+        &self.code
+    }
+
+    pub fn discharge(&self, solver: Solver) -> Result<Outcome, Box<dyn std::error::Error>> {
+        solver.call(&self.code).map(|result| CheckResult::from_string(result).into())
+    }
+}
+
+impl fmt::Display for Obligation {
+    fn fmt(&self, f: &mut fmt::Formatter) -> fmt::Result {
+        match self.kind {
+            ObligationKind::Base => write!(f, "base case")?,
+            ObligationKind::Step => write!(f, "inductive step")?,
+        }
+        if !self.conditions.is_empty() {
+            let conditions =
+                self.conditions.iter().map(ToString::to_string).collect::<Vec<_>>().join(" and ");
+            write!(f, " when {}", conditions)?;
+        }
+        Ok(())
+    }
+}
+
+/// Returns the function used for the induction, which is `a` if it is recursive and `b`
+/// otherwise, or `None` if neither of them is recursive.
+pub fn induction_function<'a>(
+    program: &Program,
+    a: &'a FuncId,
+    b: &'a FuncId,
+) -> Option<&'a FuncId> {
+    [a, b].iter().copied().find(|id| program.is_recursive(id))
+}
+
+/// Generates the obligations needed to prove that `a` and `b` are equal by induction over the
+/// recursion of one of them.
+pub fn obligations(program: &Program, a: &FuncId, b: &FuncId) -> Result<Vec<Obligation>, String> {
+    let program = program.restrict(&[a, b]);
+    let (a, b) = (crate::get(&program, a)?, crate::get(&program, b)?);
+    if a.ty != b.ty {
+        return Err(format!(
+            "`{}` and `{}` have different types",
+            program.name(&a.id),
+            program.name(&b.id)
+        ));
+    }
+
+    let (f, g) = match induction_function(&program, &a.id, &b.id) {
+        Some(id) if *id == a.id => (a, b),
+        Some(_) => (b, a),
+        None => {
+            return Err(format!(
+                "neither `{}` nor `{}` is recursive",
+                program.name(&a.id),
+                program.name(&b.id)
+            ))
+        }
+    };
+
+    let (decls, inputs) = declare_inputs(&f.ty);
+    let prelude = [program.to_smtlib(), decls.join("\n")].join("\n");
+    let expected_failed = if bmc::can_fail(&program, &g.id) {
+        Some(apply(&bmc::failed_id(&g.id), &inputs))
+    } else {
+        None
+    };
+    let expected = apply(&g.id, &inputs);
+
+    Ok(f.body
+        .paths()
+        .into_iter()
+        .map(|path| {
+            let mut hypothesis = Hypothesis { from: &f.id, to: &g.id, applied: false };
+            let mut conditions = path.conditions;
+            let mut result = path.result;
+            for condition in &mut conditions {
+                hypothesis.visit_mut_expr(condition);
+            }
+            hypothesis.visit_mut_expr(&mut result);
+
+            let kind = if hypothesis.applied { ObligationKind::Step } else { ObligationKind::Base };
+            let exprs = conditions.iter().cloned().chain(Some(result.clone())).collect::<Vec<_>>();
+            let failed = bmc::failure(&program, &exprs).map(|failure| failure.to_smtlib());
+            let outcome = same_outcome(
+                (failed, result.to_smtlib()),
+                (expected_failed.clone(), expected.clone()),
+            );
+            let code = Some(prelude.clone())
+                .into_iter()
+                .chain(conditions.iter().map(|cond| format!("(assert {})", cond.to_smtlib())))
+                .chain(vec![format!("(assert (not {}))", outcome), "(check-sat)".to_owned()])
+                .collect::<Vec<_>>()
+                .join("\n");
+
+            Obligation { kind, conditions, code }
+        })
+        .collect())
+}
+
+/// Replaces the recursive calls to `from` by calls to `to`.
+struct Hypothesis<'a> {
+    from: &'a FuncId,
+    to: &'a FuncId,
+    applied: bool,
+}
+
+impl<'a> VisitorMut for Hypothesis<'a> {
+    fn visit_mut_value(&mut self, value: &mut Value) {
+        if let Value::Function(id, _) = value {
+            if id == self.from {
+                *id = self.to.clone();
+                self.applied = true;
+            }
+        }
+    }
+}
@@ -1,48 +1,136 @@
-#![feature(box_patterns)]
+use std::collections::HashSet;
 
-use sire::sir::*;
+use sire_sir::*;
 
 use crate::smtlib::ToSmtlib;
 
+pub mod bmc;
+pub mod induction;
+pub mod model;
 pub mod smtlib;
-mod z3;
+mod solver;
+pub mod termination;
 
-pub fn check_equality(a: &FuncDef, b: &FuncDef) -> Result<CheckResult, Box<dyn std::error::Error>> {
+pub use crate::solver::Solver;
+
+/// Checks whether two functions of the program fail for the same arguments and return the same
+/// value for the others.
+pub fn check_equality(
+    program: &Program,
+    a: &FuncId,
+    b: &FuncId,
+    solver: Solver,
+) -> Result<CheckResult, Box<dyn std::error::Error>> {
+    let program = program.restrict(&[a, b]);
+    let (a, b) = (get(&program, a)?, get(&program, b)?);
     if let (Ty::Func(a_args_ty, a_params), Ty::Func(b_args_ty, b_params)) = (&a.ty, &b.ty) {
         if a_args_ty == b_args_ty && a_params == b_params {
-            let mut instances = a.body.find_datatype_instances();
-            for instance in b.body.find_datatype_instances() {
-                if !instances.contains(&instance) {
-                    instances.push(instance);
-                }
-            }
-            // Datatype declaration
-            let mut code = vec![
-                "(declare-datatypes (T1 T2) ((Tuple (tuple (first T1) (second T2)))))".to_owned(),
-                "(declare-datatypes () ((Unit (unit))))".to_owned(),
-            ];
-            // Instances of datatypes
-            code.extend_from_slice(
-                &instances
-                    .iter()
-                    .map(|ty| format!("(declare-const _ {})", ty.to_smtlib()))
-                    .collect::<Vec<_>>(),
-            );
-            // Function declarations and equality assertin
-            code.extend_from_slice(&[
-                a.to_smtlib(),
-                b.to_smtlib(),
-                gen_equality_assertion(a.def_id, b.def_id, a_args_ty, a_params),
+            let code = [
+                program.to_smtlib(),
+                // Equality assertion
+                gen_equality_assertion(&program, &a.id, &b.id, a_args_ty, a_params),
                 "(check-sat)".to_owned(),
-            ]);
-            let code = code.join("\n");
-            println!("{}", code);
-            return z3::call(&code).map(CheckResult::from_string);
+            ]
+            .join("\n");
+            return solver.call(&code).map(CheckResult::from_string);
         }
     }
     Ok(CheckResult::Unsat)
 }
 
+/// Checks that the definitions of the given function and the functions it calls are not
+/// contradictory, which can happen for example with recursive functions that do not terminate.
+pub fn check_consistency(
+    program: &Program,
+    id: &FuncId,
+    solver: Solver,
+) -> Result<CheckResult, Box<dyn std::error::Error>> {
+    get(program, id)?;
+    let code = [program.restrict(&[id]).to_smtlib(), "(check-sat)".to_owned()].join("\n");
+    solver.call(&code).map(CheckResult::from_string)
+}
+
+/// Checks whether the conjunction of the given boolean expressions can hold. Arguments and
+/// parameters are free and called functions are uninterpreted, so `Unsat` means that the
+/// conditions can never hold together.
+pub fn check_satisfiability(
+    arena: &ExprArena,
+    conditions: &[ExprId],
+    solver: Solver,
+) -> Result<CheckResult, Box<dyn std::error::Error>> {
+    let mut code = vec![smtlib::DATATYPES.to_owned()];
+    let mut visited = HashSet::new();
+    let mut stack = conditions.to_vec();
+    while let Some(id) = stack.pop() {
+        if !visited.insert(id) {
+            continue;
+        }
+        let node = arena.node(id);
+        stack.extend(node.children());
+        code.extend(match node {
+            Node::Value(Value::Arg(i, ty)) => {
+                Some(format!("(declare-const x{} {})", i, ty.to_smtlib()))
+            }
+            Node::Value(Value::ConstParam(param @ Param(_, ty))) => {
+                Some(format!("(declare-const {} {})", param.to_smtlib(), ty.to_smtlib()))
+            }
+            Node::Abort(ty) => Some(smtlib::declare_abort(ty)),
+            Node::Value(Value::Function(id, Ty::Func(args_ty, params))) => {
+                let inputs_ty = args_ty[1..]
+                    .iter()
+                    .chain(params.iter().map(|Param(_, ty)| ty))
+                    .map(Ty::to_smtlib)
+                    .collect::<Vec<_>>()
+                    .join(" ");
+                Some(format!(
+                    "(declare-fun {} ({}) {})",
+                    id.to_smtlib(),
+                    inputs_ty,
+                    args_ty[0].to_smtlib()
+                ))
+            }
+            _ => None,
+        });
+    }
+    code.extend(
+        conditions
+            .iter()
+            .map(|&condition| format!("(assert {})", smtlib::arena_to_smtlib(arena, condition))),
+    );
+    code.push("(check-sat)".to_owned());
+    solver.call(&code.join("\n")).map(CheckResult::from_string)
+}
+
+/// Declares a constant for every argument and parameter of a function of type `ty`, returning
+/// the declarations and the names of the constants.
+pub(crate) fn declare_inputs(ty: &Ty) -> (Vec<String>, Vec<String>) {
+    let (args_ty, params) = match ty {
+        Ty::Func(args_ty, params) => (args_ty, params),
+        _ => unreachable!(),
+    };
+    args_ty
+        .iter()
+        .enumerate()
+        .skip(1)
+        .map(|(i, ty)| (format!("x{}", i), ty))
+        .chain(params.iter().map(|Param(index, ty)| (format!("p{}", index), ty)))
+        .map(|(name, ty)| (format!("(declare-const {} {})", name, ty.to_smtlib()), name))
+        .unzip()
+}
+
+/// Applies the function to the given inputs.
+pub(crate) fn apply(id: &FuncId, inputs: &[String]) -> String {
+    if inputs.is_empty() {
+        id.to_smtlib()
+    } else {
+        format!("({} {})", id.to_smtlib(), inputs.join(" "))
+    }
+}
+
+fn get<'a>(program: &'a Program, id: &FuncId) -> Result<&'a FuncDef, String> {
+    program.get(id).ok_or_else(|| format!("`{}` is not part of the program", program.name(id)))
+}
+
 #[derive(Debug, PartialEq, Eq)]
 pub enum CheckResult {
     Sat,
@@ -52,7 +140,7 @@ pub enum CheckResult {
 }
 
 impl CheckResult {
-    fn from_string(s: String) -> Self {
+    pub(crate) fn from_string(s: String) -> Self {
         if s == "sat\n" {
             CheckResult::Sat
         } else if s == "unsat\n" {
@@ -65,30 +153,49 @@ impl CheckResult {
     }
 }
 
-pub fn gen_equality_assertion(a: DefId, b: DefId, args_ty: &[Ty], params: &[Param]) -> String {
-    if args_ty.len() + params.len() > 1 {
-        let (args_with_ty, args) = args_ty
-            .iter()
-            .enumerate()
-            .skip(1)
-            .map(|(i, ty)| (format!("(x{} {})", i, ty.to_smtlib()), format!("x{}", i)))
-            .chain(params.iter().map(|Param(index, ty)| {
-                (format!("(p{} {})", index, ty.to_smtlib()), format!("p{}", index))
-            }))
-            .unzip::<String, String, Vec<String>, Vec<String>>();
-
-        let args_with_ty = args_with_ty.join(" ");
-        let args = args.join(" ");
+/// Asserts that both functions fail for the same arguments and return the same value for the
+/// others.
+pub fn gen_equality_assertion(
+    program: &Program,
+    a: &FuncId,
+    b: &FuncId,
+    args_ty: &[Ty],
+    params: &[Param],
+) -> String {
+    let (args_with_ty, args) = args_ty
+        .iter()
+        .enumerate()
+        .skip(1)
+        .map(|(i, ty)| (format!("(x{} {})", i, ty.to_smtlib()), format!("x{}", i)))
+        .chain(params.iter().map(|Param(index, ty)| {
+            (format!("(p{} {})", index, ty.to_smtlib()), format!("p{}", index))
+        }))
+        .unzip::<String, String, Vec<String>, Vec<String>>();
 
-        format!(
-            "(assert (forall ({}) (= ({} {}) ({} {}))))",
-            args_with_ty,
-            a.to_smtlib(),
-            args,
-            b.to_smtlib(),
-            args
-        )
+    let outcome = |id: &FuncId| {
+        let failed =
+            if bmc::can_fail(program, id) { Some(apply(&bmc::failed_id(id), &args)) } else { None };
+        (failed, apply(id, &args))
+    };
+    let equality = same_outcome(outcome(a), outcome(b));
+    if args.is_empty() {
+        format!("(assert {})", equality)
     } else {
-        format!("(assert (= {} {}))", a.to_smtlib(), b.to_smtlib(),)
+        format!("(assert (forall ({}) {}))", args_with_ty.join(" "), equality)
+    }
+}
+
+/// A formula that holds if two evaluations, given by whether they fail and their result, either
+/// both fail or both succeed with equal results. Evaluations that cannot fail have no flag.
+pub(crate) fn same_outcome(
+    (a_failed, a): (Option<String>, String),
+    (b_failed, b): (Option<String>, String),
+) -> String {
+    let equal = format!("(= {} {})", a, b);
+    if a_failed.is_none() && b_failed.is_none() {
+        return equal;
     }
+    let a_failed = a_failed.unwrap_or_else(|| "false".to_owned());
+    let b_failed = b_failed.unwrap_or_else(|| "false".to_owned());
+    format!("(and (= {} {}) (or {} {}))", a_failed, b_failed, a_failed, equal)
 }
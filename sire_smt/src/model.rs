@@ -0,0 +1,128 @@
+//! Concrete inputs of a function satisfying some conditions, read from the models found by the
+//! solver.
+
+use sire_sir::*;
+
+use crate::smtlib::ToSmtlib;
+use crate::{bmc, declare_inputs, get, CheckResult, Solver};
+
+/// Asks the solver for inputs of the function that satisfy the conditions, which are its
+/// arguments followed by its const parameters. Returns `None` if no such inputs were found.
+pub fn find_inputs(
+    program: &Program,
+    id: &FuncId,
+    conditions: &[Expr],
+    solver: Solver,
+) -> Result<Option<Vec<Value>>, Box<dyn std::error::Error>> {
+    let func = get(program, id)?;
+    let (args_ty, params) = match &func.ty {
+        Ty::Func(args_ty, params) => (args_ty, params),
+        _ => unreachable!(),
+    };
+    let (declarations, names) = declare_inputs(&func.ty);
+    let mut code = vec![program.restrict(&[id]).to_smtlib()];
+    code.extend(declarations);
+    code.extend(conditions.iter().map(|condition| format!("(assert {})", condition.to_smtlib())));
+    code.push("(check-sat)".to_owned());
+    if !names.is_empty() {
+        code.push(format!("(get-value ({}))", names.join(" ")));
+    }
+
+    let output = solver.call(&code.join("\n"))?;
+    let (status, model) = output.split_at(output.find('\n').map(|i| i + 1).unwrap_or(0));
+    match CheckResult::from_string(status.to_owned()) {
+        CheckResult::Sat => (),
+        CheckResult::Unsat | CheckResult::Undecided => return Ok(None),
+        CheckResult::Unknown(_) => {
+            return Err(format!("unexpected solver output: {}", output).into())
+        }
+    }
+
+    if names.is_empty() {
+        return Ok(Some(Vec::new()));
+    }
+
+    let tys = args_ty[1..].iter().chain(params.iter().map(|Param(_, ty)| ty));
+    let sexps = parse(model).unwrap_or_default();
+    let inputs = match sexps.as_slice() {
+        [Sexp::List(pairs)] if pairs.len() == names.len() => pairs
+            .iter()
+            .zip(tys)
+            .map(|(pair, ty)| match pair {
+                Sexp::List(pair) if pair.len() == 2 => parse_value(&pair[1], ty),
+                _ => None,
+            })
+            .collect::<Option<Vec<_>>>(),
+        _ => None,
+    };
+
+    inputs.map(Some).ok_or_else(|| format!("unexpected solver output: {}", output).into())
+}
+
+/// Looks for inputs for which the evaluation of the function fails, because it reaches an
+/// `Abort` or a failing assertion, either in its body or in the functions it calls.
+pub fn find_abort(
+    program: &Program,
+    id: &FuncId,
+    solver: Solver,
+) -> Result<Option<Vec<Value>>, Box<dyn std::error::Error>> {
+    match bmc::failure(program, std::slice::from_ref(&get(program, id)?.body)) {
+        Some(failure) => find_inputs(program, id, &[failure], solver),
+        None => Ok(None),
+    }
+}
+
+enum Sexp {
+    Atom(String),
+    List(Vec<Sexp>),
+}
+
+/// Parses a sequence of s-expressions.
+fn parse(code: &str) -> Option<Vec<Sexp>> {
+    let spaced = code.replace('(', " ( ").replace(')', " ) ");
+    let mut stack = vec![Vec::new()];
+    for token in spaced.split_whitespace() {
+        match token {
+            "(" => stack.push(Vec::new()),
+            ")" => {
+                let list = stack.pop()?;
+                stack.last_mut()?.push(Sexp::List(list));
+            }
+            atom => stack.last_mut()?.push(Sexp::Atom(atom.to_owned())),
+        }
+    }
+    match stack.len() {
+        1 => stack.pop(),
+        _ => None,
+    }
+}
+
+/// Reads a boolean or a bit-vector of the given type, as printed by z3 or cvc4.
+fn parse_value(sexp: &Sexp, ty: &Ty) -> Option<Value> {
+    let bits = match (sexp, ty) {
+        (Sexp::Atom(atom), Ty::Bool) => match atom.as_str() {
+            "true" => 1,
+            "false" => 0,
+            _ => return None,
+        },
+        (Sexp::Atom(atom), Ty::Int(_)) | (Sexp::Atom(atom), Ty::Uint(_)) => {
+            let radix = if atom.starts_with("#x") {
+                16
+            } else if atom.starts_with("#b") {
+                2
+            } else {
+                return None;
+            };
+            u128::from_str_radix(&atom[2..], radix).ok()?
+        }
+        // `(_ bvN width)`
+        (Sexp::List(list), Ty::Int(_)) | (Sexp::List(list), Ty::Uint(_)) => match list.as_slice() {
+            [Sexp::Atom(underscore), Sexp::Atom(value), _] if underscore == "_" => {
+                value.trim_start_matches("bv").parse().ok()?
+            }
+            _ => return None,
+        },
+        _ => return None,
+    };
+    Some(Value::Const(bits, ty.clone()))
+}
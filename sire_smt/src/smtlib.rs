@@ -1,40 +1,116 @@
-use sire::sir::*;
+use std::collections::HashMap;
+
+use sire_sir::*;
+
+use crate::bmc;
 
 pub trait ToSmtlib {
     fn to_smtlib(&self) -> String;
 }
 
-impl ToSmtlib for FuncDef {
+/// Declares the datatypes used by the program and defines its functions, every one of them
+/// after the functions it calls. The functions that can fail are followed by their `f@failed`
+/// flags.
+impl ToSmtlib for Program {
     fn to_smtlib(&self) -> String {
-        let body = self.body.to_smtlib();
-        let (args, params) = match &self.ty {
-            Ty::Func(args, params) => (args, params),
-            _ => unreachable!(),
-        };
-
-        let ret_ty = args[0].to_smtlib();
-
-        let args_with_ty = args
-            .iter()
-            .enumerate()
-            .skip(1)
-            .map(|(i, ty)| format!("(x{} {})", i, ty.to_smtlib()))
-            .chain(params.iter().map(|Param(index, ty)| format!("(p{} {})", index, ty.to_smtlib())))
-            .collect::<Vec<String>>()
-            .join(" ");
+        let mut code = datatype_declarations(self);
+        // Function declarations, grouping mutually recursive functions together
+        for component in self.components() {
+            code.extend(component.iter().map(|func| format!("; {}", self.name(&func.id))));
+            code.push(define_component(&component));
+            let flags = component
+                .iter()
+                .filter_map(|func| bmc::failed_flag(self, func))
+                .collect::<Vec<_>>();
+            if !flags.is_empty() {
+                code.push(define_component(&flags.iter().collect::<Vec<_>>()));
+            }
+        }
+        code.join("\n")
+    }
+}
 
+/// Declares the tuple and unit datatypes, using the syntax of SMT-LIB 2.6 so every supported
+/// solver accepts it. Tuples are nested pairs, instantiated where they are used.
+pub(crate) const DATATYPES: &str = "(declare-datatypes ((Tuple 2) (Unit 0)) \
+                                    ((par (T1 T2) ((tuple (first T1) (second T2)))) ((unit))))";
+
+/// Declares the datatypes used by the functions of the program.
+pub(crate) fn datatype_declarations(program: &Program) -> Vec<String> {
+    let mut code = vec![DATATYPES.to_owned()];
+    // Results of aborted evaluations
+    let mut arena = ExprArena::default();
+    for func in program.funcs() {
+        arena.insert(&func.body);
+    }
+    let mut aborts = Vec::new();
+    for node in arena.nodes() {
+        if let Node::Abort(ty) = node {
+            if !aborts.contains(ty) {
+                aborts.push(ty.clone());
+            }
+        }
+    }
+    code.extend(aborts.iter().map(declare_abort));
+    code
+}
+
+/// Every evaluation that aborts gives an unconstrained value, which is a constant for each type.
+/// The value is meaningless: whether the evaluation aborted is told by the `f@failed` flags.
+pub(crate) fn declare_abort(ty: &Ty) -> String {
+    format!("(declare-const {} {})", abort_constant(ty), ty.to_smtlib())
+}
+
+fn abort_constant(ty: &Ty) -> String {
+    format!("|abort {}|", ty)
+}
+
+impl ToSmtlib for FuncDef {
+    fn to_smtlib(&self) -> String {
         let def = if self.is_recursive() { "define-fun-rec" } else { "define-fun" };
-        format!(
-            "({def} {name} ({args_with_ty}) {ret_ty} {body})",
-            def = def,
-            name = self.def_id.to_smtlib(),
-            ret_ty = ret_ty,
-            args_with_ty = args_with_ty,
-            body = body
-        )
+        format!("({} {} {})", def, signature(self), self.body.to_smtlib())
+    }
+}
+
+/// Defines a strongly connected component of the call graph, using a single `define-funs-rec`
+/// block if it has several functions so they can reference each other.
+pub fn define_component(component: &[&FuncDef]) -> String {
+    match component {
+        [func] => func.to_smtlib(),
+        _ => {
+            let signatures = component
+                .iter()
+                .map(|func| format!("({})", signature(func)))
+                .collect::<Vec<_>>()
+                .join(" ");
+            let bodies =
+                component.iter().map(|func| func.body.to_smtlib()).collect::<Vec<_>>().join(" ");
+            format!("(define-funs-rec ({}) ({}))", signatures, bodies)
+        }
     }
 }
 
+/// The name, arguments and return type of a function definition.
+fn signature(func: &FuncDef) -> String {
+    let (args, params) = match &func.ty {
+        Ty::Func(args, params) => (args, params),
+        _ => unreachable!(),
+    };
+
+    let ret_ty = args[0].to_smtlib();
+
+    let args_with_ty = args
+        .iter()
+        .enumerate()
+        .skip(1)
+        .map(|(i, ty)| format!("(x{} {})", i, ty.to_smtlib()))
+        .chain(params.iter().map(|Param(index, ty)| format!("(p{} {})", index, ty.to_smtlib())))
+        .collect::<Vec<String>>()
+        .join(" ");
+
+    format!("{} ({}) {}", func.id.to_smtlib(), args_with_ty, ret_ty)
+}
+
 impl ToSmtlib for Param {
     fn to_smtlib(&self) -> String {
         let Param(index, _) = self;
@@ -51,7 +127,7 @@ impl ToSmtlib for Ty {
                 if let Some(first) = fields.next() {
                     let mut buffer = first.to_smtlib();
                     for field in fields {
-                        buffer = format!("(Tuple {} {})", field, buffer);
+                        buffer = format!("(Tuple {} {})", field.to_smtlib(), buffer);
                     }
                     buffer
                 } else {
@@ -63,9 +139,10 @@ impl ToSmtlib for Ty {
     }
 }
 
-impl ToSmtlib for DefId {
+impl ToSmtlib for FuncId {
     fn to_smtlib(&self) -> String {
-        format!("func_{}_{}", self.krate.as_u32(), self.index.as_u32())
+        let FuncId(name) = self;
+        name.clone()
     }
 }
 
@@ -83,15 +160,55 @@ impl ToSmtlib for Value {
     }
 }
 
+/// Subexpressions that are used more than once are bound with `let`, so the size of the output
+/// is linear in the number of distinct subexpressions.
 impl ToSmtlib for Expr {
     fn to_smtlib(&self) -> String {
-        match self {
-            Expr::Value(value) => value.to_smtlib(),
-            Expr::BinaryOp(op, e1, e2) => {
-                let smt_op = match e1.ty() {
+        let mut arena = ExprArena::default();
+        let root = arena.insert(self);
+        arena_to_smtlib(&arena, root)
+    }
+}
+
+/// Translates the expression with the given root, binding its shared subexpressions with `let`.
+pub fn arena_to_smtlib(arena: &ExprArena, root: ExprId) -> String {
+    let mut printer = Printer { arena, names: HashMap::new() };
+
+    let mut bindings = Vec::new();
+    for id in arena.shared(root) {
+        if let Node::Value(_) = arena.node(id) {
+            continue;
+        }
+        let name = format!("let@{}", id.index());
+        bindings.push((name.clone(), printer.print(id)));
+        printer.names.insert(id, name);
+    }
+
+    let mut code = printer.print(root);
+    for (name, def) in bindings.into_iter().rev() {
+        code = format!("(let (({} {})) {})", name, def, code);
+    }
+    code
+}
+
+struct Printer<'a> {
+    arena: &'a ExprArena,
+    names: HashMap<ExprId, String>,
+}
+
+impl<'a> Printer<'a> {
+    fn print(&self, id: ExprId) -> String {
+        if let Some(name) = self.names.get(&id) {
+            return name.clone();
+        }
+
+        match self.arena.node(id) {
+            Node::Value(value) => value.to_smtlib(),
+            Node::BinaryOp(op, e1, e2) => {
+                let smt_op = match self.arena.ty(*e1) {
                     Ty::Bool => match op {
                         BinOp::Eq => "=",
-                        BinOp::Ne => "!=",
+                        BinOp::Ne => "distinct",
                         _ => unreachable!(),
                     },
                     Ty::Int(_) => match op {
@@ -103,7 +220,7 @@ impl ToSmtlib for Expr {
                         BinOp::Eq => "=",
                         BinOp::Lt => "bvslt",
                         BinOp::Le => "bvsle",
-                        BinOp::Ne => "!=",
+                        BinOp::Ne => "distinct",
                         BinOp::Ge => "bvsge",
                         BinOp::Gt => "bvsgt",
                         _ => unreachable!(),
@@ -117,51 +234,65 @@ impl ToSmtlib for Expr {
                         BinOp::Eq => "=",
                         BinOp::Lt => "bvult",
                         BinOp::Le => "bvule",
-                        BinOp::Ne => "!=",
+                        BinOp::Ne => "distinct",
                         BinOp::Ge => "bvuge",
                         BinOp::Gt => "bvugt",
                         _ => unreachable!(),
                     },
                     _ => unreachable!(),
                 };
-                format!("({} {} {})", smt_op, e1.to_smtlib(), e2.to_smtlib())
+                format!("({} {} {})", smt_op, self.print(*e1), self.print(*e2))
             }
-            Expr::Apply(f, es) => format!(
+            Node::Apply(f, es) => format!(
                 "({} {})",
-                f.to_smtlib(),
-                es.iter().map(ToSmtlib::to_smtlib).collect::<Vec<_>>().join(" ")
+                self.print(*f),
+                es.iter().map(|&e| self.print(e)).collect::<Vec<_>>().join(" ")
             ),
-            Expr::Switch(val, cs, bs) => {
-                if let Ty::Bool = val.ty() {
-                    format!("(ite {} {} {})", val.to_smtlib(), bs[1].to_smtlib(), bs[0].to_smtlib())
+            Node::Switch(val, cs, bs) => {
+                // A boolean switch with a single constant arm is a plain `ite`.
+                let bool_arm = match cs[..] {
+                    [c] => match self.arena.node(c) {
+                        Node::Value(Value::Const(b, Ty::Bool)) => Some(*b != 0),
+                        _ => None,
+                    },
+                    _ => None,
+                };
+                if let Some(arm) = bool_arm {
+                    let (then, otherwise) = if arm { (bs[0], bs[1]) } else { (bs[1], bs[0]) };
+                    format!(
+                        "(ite {} {} {})",
+                        self.print(*val),
+                        self.print(then),
+                        self.print(otherwise)
+                    )
                 } else {
-                    let mut cond = bs.last().unwrap().to_smtlib();
+                    let mut cond = self.print(*bs.last().unwrap());
                     for i in (0..cs.len()).rev() {
                         cond = format!(
                             "(ite (= {} {}) {} {})",
-                            val.to_smtlib(),
-                            cs[i].to_smtlib(),
-                            bs[i].to_smtlib(),
+                            self.print(*val),
+                            self.print(cs[i]),
+                            self.print(bs[i]),
                             cond
                         );
                     }
                     cond
                 }
             }
-            Expr::Tuple(fields) => {
+            Node::Tuple(fields) => {
                 let mut fields = fields.iter().rev();
-                if let Some(first) = fields.next() {
-                    let mut buffer = first.to_smtlib();
-                    for field in fields {
-                        buffer = format!("(tuple {} {})", field.to_smtlib(), buffer);
+                if let Some(&first) = fields.next() {
+                    let mut buffer = self.print(first);
+                    for &field in fields {
+                        buffer = format!("(tuple {} {})", self.print(field), buffer);
                     }
                     buffer
                 } else {
                     "unit".to_owned()
                 }
             }
-            Expr::Projection(box tuple, index) => {
-                let mut buffer = tuple.to_smtlib();
+            Node::Projection(tuple, index) => {
+                let mut buffer = self.print(*tuple);
                 match index {
                     0 => buffer = format!("(first {})", buffer),
                     1 => buffer = format!("(second {})", buffer),
@@ -171,7 +302,8 @@ impl ToSmtlib for Expr {
                 buffer
             }
             // FIXME: Handle assertions correctly
-            Expr::Assert(_, result) => result.to_smtlib(),
+            Node::Assert(_, result) => self.print(*result),
+            Node::Abort(ty) => abort_constant(ty),
             _ => unimplemented!(),
         }
     }
@@ -0,0 +1,69 @@
+use std::fmt;
+use std::io::{Read, Write};
+use std::process::{Command, Stdio};
+use std::str::FromStr;
+
+/// An external SMT solver reading SMT-LIB code from its standard input.
+#[derive(Clone, Copy, Debug, PartialEq, Eq)]
+pub enum Solver {
+    Z3,
+    Cvc4,
+}
+
+#[allow(clippy::derivable_impls)]
+impl Default for Solver {
+    fn default() -> Self {
+        Solver::Z3
+    }
+}
+
+impl Solver {
+    pub fn call(self, code: &str) -> Result<String, Box<dyn std::error::Error>> {
+        let mut buffer = String::new();
+
+        let child = self.command().stdin(Stdio::piped()).stdout(Stdio::piped()).spawn()?;
+
+        child.stdin.expect("stdin is none").write_all(code.as_bytes())?;
+
+        child.stdout.expect("stdout is none").read_to_string(&mut buffer)?;
+
+        Ok(buffer)
+    }
+
+    fn command(self) -> Command {
+        match self {
+            Solver::Z3 => {
+                let mut command = Command::new("z3");
+                command.arg("-in");
+                command
+            }
+            Solver::Cvc4 => {
+                let mut command = Command::new("cvc4");
+                // Models are needed to read the values of inputs.
+                command.arg("--lang=smt2.6").arg("--produce-models");
+                command
+            }
+        }
+    }
+}
+
+impl FromStr for Solver {
+    type Err = String;
+
+    fn from_str(s: &str) -> Result<Self, Self::Err> {
+        match s {
+            "z3" => Ok(Solver::Z3),
+            "cvc4" => Ok(Solver::Cvc4),
+            _ => Err(format!("unknown solver `{}`", s)),
+        }
+    }
+}
+
+impl fmt::Display for Solver {
+    fn fmt(&self, f: &mut fmt::Formatter<'_>) -> fmt::Result {
+        match self {
+            Solver::Z3 => write!(f, "z3"),
+            Solver::Cvc4 => write!(f, "cvc4"),
+        }
+    }
+}
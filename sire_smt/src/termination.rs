@@ -0,0 +1,164 @@
+//! Termination checking for recursive functions.
+//!
+//! A component of mutually recursive functions terminates if some argument, at the same position
+//! for all of them, strictly decreases at every recursive call. Arguments are bit-vectors, so
+//! every strictly decreasing sequence is finite. Each call site gives an obligation where the
+//! conditions of the path leading to the call are assumed.
+//!
+//! The definition of a recursive function that does not terminate can be contradictory, which
+//! would make every obligation hold, so the recursive functions are left uninterpreted.
+
+use std::rc::Rc;
+
+use sire_sir::*;
+
+use crate::bmc::declare;
+use crate::smtlib::{datatype_declarations, ToSmtlib};
+use crate::{declare_inputs, CheckResult, Solver};
+
+#[derive(Debug, PartialEq, Eq)]
+pub enum Termination {
+    NonRecursive,
+    /// The argument with the given index decreases at every recursive call.
+    Decreasing(usize),
+    Unproved,
+}
+
+/// Tries to find an argument of the function that decreases at every recursive call of its
+/// component.
+pub fn check_termination(
+    program: &Program,
+    id: &FuncId,
+    solver: Solver,
+) -> Result<Termination, Box<dyn std::error::Error>> {
+    if !program.is_recursive(id) {
+        return Ok(Termination::NonRecursive);
+    }
+    let program = program.restrict(&[id]);
+    let component = program
+        .components()
+        .into_iter()
+        .find(|component| component.iter().any(|func| func.id == *id))
+        .unwrap();
+
+    let sites = component
+        .iter()
+        .flat_map(|func| {
+            call_sites(&func.body, &component).into_iter().map(move |site| (*func, site))
+        })
+        .collect::<Vec<_>>();
+
+    let prelude = uninterpreted_recursion(&program);
+    for index in measure_candidates(&component) {
+        let mut decreases = true;
+        for (func, (conditions, args)) in &sites {
+            let query = decrease_query(&prelude, func, conditions, args, index);
+            if CheckResult::from_string(solver.call(&query)?) != CheckResult::Unsat {
+                decreases = false;
+                break;
+            }
+        }
+        if decreases {
+            return Ok(Termination::Decreasing(index));
+        }
+    }
+
+    Ok(Termination::Unproved)
+}
+
+/// The indices of the integer arguments shared by every function in the component.
+// `matches!` is too recent for the toolchain of the evaluator.
+#[allow(clippy::match_like_matches_macro)]
+fn measure_candidates(component: &[&FuncDef]) -> Vec<usize> {
+    let args_ty = |func: &FuncDef| match &func.ty {
+        Ty::Func(args_ty, _) => args_ty.clone(),
+        _ => unreachable!(),
+    };
+    let first = args_ty(component[0]);
+    (1..first.len())
+        .filter(|&i| match first[i] {
+            Ty::Int(_) | Ty::Uint(_) => true,
+            _ => false,
+        })
+        .filter(|&i| component.iter().all(|func| args_ty(func).get(i) == Some(&first[i])))
+        .collect()
+}
+
+/// Returns the conditions leading to every call to a function in the component, together with
+/// the arguments of the call.
+fn call_sites(body: &Expr, component: &[&FuncDef]) -> Vec<(Vec<Expr>, Vec<Rc<Expr>>)> {
+    let mut sites = Vec::new();
+    for path in body.paths() {
+        // A call in a condition can only assume the conditions checked before it.
+        for (i, condition) in path.conditions.iter().enumerate() {
+            for args in recursive_calls(condition, component) {
+                sites.push((path.conditions[..i].to_vec(), args));
+            }
+        }
+        for args in recursive_calls(&path.result, component) {
+            sites.push((path.conditions.clone(), args));
+        }
+    }
+    sites.dedup();
+    sites
+}
+
+fn recursive_calls(expr: &Expr, component: &[&FuncDef]) -> Vec<Vec<Rc<Expr>>> {
+    struct Calls<'a> {
+        component: &'a [&'a FuncDef],
+        args: Vec<Vec<Rc<Expr>>>,
+    }
+
+    impl<'a> Visitor for Calls<'a> {
+        fn visit_apply(&mut self, func: &Expr, args: &[Rc<Expr>]) {
+            self.super_apply(func, args);
+            if let Expr::Value(Value::Function(id, _)) = func {
+                if self.component.iter().any(|func| func.id == *id) {
+                    self.args.push(args.to_vec());
+                }
+            }
+        }
+    }
+
+    let mut calls = Calls { component, args: Vec::new() };
+    calls.visit_expr(expr);
+    calls.args
+}
+
+/// Defines the functions of the program that are not recursive and only declares the others.
+fn uninterpreted_recursion(program: &Program) -> String {
+    let mut code = datatype_declarations(program);
+    for component in program.components() {
+        code.extend(component.iter().map(|func| format!("; {}", program.name(&func.id))));
+        if program.is_recursive(&component[0].id) {
+            code.extend(component.iter().map(|func| declare(func, &func.id)));
+        } else {
+            code.push(component[0].to_smtlib());
+        }
+    }
+    code.join("\n")
+}
+
+/// A query that is unsatisfiable if the argument at `index` decreases at the call site.
+fn decrease_query(
+    prelude: &str,
+    func: &FuncDef,
+    conditions: &[Expr],
+    args: &[Rc<Expr>],
+    index: usize,
+) -> String {
+    let (decls, _) = declare_inputs(&func.ty);
+    let arg = match &func.ty {
+        Ty::Func(args_ty, _) => Expr::Value(Value::Arg(index, args_ty[index].clone())),
+        _ => unreachable!(),
+    };
+    let decreases = Expr::BinaryOp(BinOp::Lt, args[index - 1].clone(), Rc::new(arg));
+
+    Some(prelude.to_owned())
+        .into_iter()
+        .chain(decls)
+        .chain(conditions.iter().map(|condition| format!("(assert {})", condition.to_smtlib())))
+        .chain(vec![format!("(assert (not {}))", decreases.to_smtlib()), "(check-sat)".to_owned()])
+        .collect::<Vec<_>>()
+        .join("\n")
+}
@@ -0,0 +1,34 @@
+use sire_sir::*;
+use sire_smt::bmc::unroll;
+
+const EVEN_ODD: &str = "
+    (defun is_even[] (bool (uint 8))
+        (switch (= _1 (const (uint 8) 0))
+            ((const bool 0) -> (is_odd (- _1 (const (uint 8) 1))))
+            (else -> (const bool 1))))
+    (defun is_odd[] (bool (uint 8))
+        (switch (= _1 (const (uint 8) 0))
+            ((const bool 0) -> (is_even (- _1 (const (uint 8) 1))))
+            (else -> (const bool 0))))
+    (defun check[] (bool (uint 8)) (is_even _1))";
+
+#[test]
+fn test_unroll() {
+    let program = Program::new(parse_func_defs(EVEN_ODD).unwrap(), SymbolTable::default());
+    let code = unroll(&program, 2);
+    let lines = code.lines().collect::<Vec<_>>();
+
+    for line in &[
+        "(declare-fun is_even@0 ((_ BitVec 8)) Bool)",
+        "(define-fun is_even@1 ((x1 (_ BitVec 8))) Bool \
+         (ite (= x1 (_ bv0 8)) true (is_odd@0 (bvsub x1 (_ bv1 8)))))",
+        "(define-fun is_even@1@exceeded ((x1 (_ BitVec 8))) Bool (ite (= x1 (_ bv0 8)) false true))",
+        "(define-fun is_odd@2@exceeded ((x1 (_ BitVec 8))) Bool \
+         (ite (= x1 (_ bv0 8)) false (is_even@1@exceeded (bvsub x1 (_ bv1 8)))))",
+        "(define-fun check ((x1 (_ BitVec 8))) Bool (is_even@2 x1))",
+        "(define-fun check@exceeded ((x1 (_ BitVec 8))) Bool (is_even@2@exceeded x1))",
+    ] {
+        assert!(lines.contains(line), "missing `{}` in\n{}", line, code);
+    }
+    assert!(!code.contains("define-fun-rec"));
+}
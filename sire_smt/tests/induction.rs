@@ -0,0 +1,34 @@
+use sire_sir::*;
+use sire_smt::induction::{obligations, ObligationKind};
+
+#[test]
+fn test_obligations() {
+    let program = Program::new(
+        parse_func_defs(
+            "(defun distance[] ((int 32) (int 32) (int 32))
+                (switch (> _1 _2)
+                    ((const bool 0) -> (- _2 _1))
+                    (else -> (- _1 _2))))
+            (defun rec_dist[] ((int 32) (int 32) (int 32))
+                (switch (> _1 _2)
+                    ((const bool 0) -> (rec_dist _2 _1))
+                    (else -> (- _1 _2))))",
+        )
+        .unwrap(),
+        SymbolTable::default(),
+    );
+
+    let distance = FuncId("distance".to_owned());
+    let rec_dist = FuncId("rec_dist".to_owned());
+    let obligations = obligations(&program, &distance, &rec_dist).unwrap();
+
+    assert_eq!(
+        obligations.iter().map(|obligation| obligation.kind).collect::<Vec<_>>(),
+        vec![ObligationKind::Step, ObligationKind::Base]
+    );
+    assert_eq!(obligations[0].to_string(), "inductive step when (= (> _1 _2) (const bool 0))");
+    assert!(obligations[0]
+        .code()
+        .ends_with("(assert (not (= (distance x2 x1) (distance x1 x2))))\n(check-sat)"));
+    assert!(obligations[1].code().contains("(assert (distinct (bvsgt x1 x2) false))"));
+}
@@ -1,54 +1,163 @@
-use rustc::hir::def_id::{DefIndex, CrateNum};
+use std::rc::Rc;
 
-use sire_smt::check_equality;
-use sire::sir::*;
+use sire_sir::*;
+use sire_smt::model::find_abort;
+use sire_smt::{
+    check_consistency, check_equality, check_satisfiability, gen_equality_assertion, CheckResult,
+    Solver,
+};
 
 #[test]
 fn test_equality_sat() -> Result<(), Box<dyn std::error::Error>> {
     let a = FuncDef {
-        def_id: DefId { krate: CrateNum::new(0), index: DefIndex::from_usize(1)},
+        id: FuncId("a".to_owned()),
         body: Expr::BinaryOp(
             BinOp::Add,
-            Box::new(Expr::Value(Value::Arg(1, Ty::Uint(32)))),
-            Box::new(Expr::Value(Value::Arg(1, Ty::Uint(32)))),
+            Rc::new(Expr::Value(Value::Arg(1, Ty::Uint(32)))),
+            Rc::new(Expr::Value(Value::Arg(1, Ty::Uint(32)))),
         ),
-        ty: Ty::Func(vec![Ty::Uint(32), Ty::Uint(32)]),
+        ty: Ty::Func(vec![Ty::Uint(32), Ty::Uint(32)], vec![]),
     };
 
     let b = FuncDef {
-        def_id: DefId { krate: CrateNum::new(0), index: DefIndex::from_usize(2)},
+        id: FuncId("b".to_owned()),
         body: Expr::BinaryOp(
             BinOp::Mul,
-            Box::new(Expr::Value(Value::Const(2, Ty::Uint(32)))),
-            Box::new(Expr::Value(Value::Arg(1, Ty::Uint(32)))),
+            Rc::new(Expr::Value(Value::Const(2, Ty::Uint(32)))),
+            Rc::new(Expr::Value(Value::Arg(1, Ty::Uint(32)))),
         ),
-        ty: Ty::Func(vec![Ty::Uint(32), Ty::Uint(32)]),
+        ty: Ty::Func(vec![Ty::Uint(32), Ty::Uint(32)], vec![]),
     };
 
-    assert_eq!(sire_smt::CheckResult::Sat, check_equality(&a, &b)?);
+    let (a_id, b_id) = (a.id.clone(), b.id.clone());
+    let program = Program::new(vec![a, b], SymbolTable::default());
+
+    assert_eq!(sire_smt::CheckResult::Sat, check_equality(&program, &a_id, &b_id, Solver::Z3)?);
 
     Ok(())
 }
 
 #[test]
-fn test_equality_unsat()  -> Result<(), Box<dyn std::error::Error>> {
+fn test_equality_unsat() -> Result<(), Box<dyn std::error::Error>> {
     let a = FuncDef {
-        def_id: DefId { krate: CrateNum::new(0), index: DefIndex::from_usize(1)},
+        id: FuncId("a".to_owned()),
         body: Expr::BinaryOp(
             BinOp::Add,
-            Box::new(Expr::Value(Value::Arg(1, Ty::Uint(32)))),
-            Box::new(Expr::Value(Value::Arg(1, Ty::Uint(32)))),
+            Rc::new(Expr::Value(Value::Arg(1, Ty::Uint(32)))),
+            Rc::new(Expr::Value(Value::Arg(1, Ty::Uint(32)))),
         ),
-        ty: Ty::Func(vec![Ty::Uint(32), Ty::Uint(32)]),
+        ty: Ty::Func(vec![Ty::Uint(32), Ty::Uint(32)], vec![]),
     };
 
     let b = FuncDef {
-        def_id: DefId { krate: CrateNum::new(0), index: DefIndex::from_usize(2)},
+        id: FuncId("b".to_owned()),
         body: Expr::Value(Value::Arg(1, Ty::Uint(32))),
-        ty: Ty::Func(vec![Ty::Uint(32), Ty::Uint(32)]),
+        ty: Ty::Func(vec![Ty::Uint(32), Ty::Uint(32)], vec![]),
+    };
+
+    let (a_id, b_id) = (a.id.clone(), b.id.clone());
+    let program = Program::new(vec![a, b], SymbolTable::default());
+
+    assert_eq!(sire_smt::CheckResult::Unsat, check_equality(&program, &a_id, &b_id, Solver::Z3)?);
+
+    Ok(())
+}
+
+/// `f` panics for `0`, so it is not equivalent to the identity even though the value of the abort
+/// is unconstrained.
+const PANICS_FOR_ZERO: &str = "
+    (defun f[] ((uint 8) (uint 8))
+        (switch (= _1 (const (uint 8) 0))
+            ((const bool 0) -> _1)
+            (else -> (abort (uint 8)))))
+    (defun g[] ((uint 8) (uint 8)) _1)";
+
+#[test]
+fn test_equality_assertion_with_abort() {
+    let program = Program::new(parse_func_defs(PANICS_FOR_ZERO).unwrap(), SymbolTable::default());
+    let (f, g) = (FuncId("f".to_owned()), FuncId("g".to_owned()));
+
+    assert_eq!(
+        gen_equality_assertion(&program, &f, &g, &[Ty::Uint(8), Ty::Uint(8)], &[]),
+        "(assert (forall ((x1 (_ BitVec 8))) \
+         (and (= (f@failed x1) false) (or (f@failed x1) (= (f x1) (g x1))))))"
+    );
+}
+
+#[test]
+fn test_equality_with_abort() -> Result<(), Box<dyn std::error::Error>> {
+    let program = Program::new(parse_func_defs(PANICS_FOR_ZERO).unwrap(), SymbolTable::default());
+    let (f, g) = (FuncId("f".to_owned()), FuncId("g".to_owned()));
+
+    assert_eq!(CheckResult::Unsat, check_equality(&program, &f, &g, Solver::Z3)?);
+
+    Ok(())
+}
+
+#[test]
+fn test_satisfiability_with_call() -> Result<(), Box<dyn std::error::Error>> {
+    let mut arena = ExprArena::default();
+    // The conditions of the arms of a switch on `f(x)`, where `f` is not part of the program.
+    let f_ty = Ty::Func(vec![Ty::Uint(8), Ty::Uint(8)], vec![]);
+    let f = arena.intern(Node::Value(Value::Function(FuncId("f".to_owned()), f_ty)));
+    let x = arena.intern(Node::Value(Value::Arg(1, Ty::Uint(8))));
+    let call = arena.intern(Node::Apply(f, vec![x]));
+    let zero = arena.intern(Node::Value(Value::Const(0, Ty::Uint(8))));
+    let is_zero = arena.intern(Node::BinaryOp(BinOp::Eq, call, zero));
+    let is_not_zero = arena.intern(Node::BinaryOp(BinOp::Ne, call, zero));
+
+    assert_eq!(CheckResult::Sat, check_satisfiability(&arena, &[is_zero], Solver::Z3)?);
+    assert_eq!(
+        CheckResult::Unsat,
+        check_satisfiability(&arena, &[is_zero, is_not_zero], Solver::Z3)?
+    );
+
+    Ok(())
+}
+
+#[test]
+fn test_every_solver() -> Result<(), Box<dyn std::error::Error>> {
+    // `f` returns a tuple and aborts for `0`, and `g` returns the unit value.
+    let pair = Ty::Tuple(vec![Ty::Uint(8), Ty::Bool]);
+    let x1 = Expr::Value(Value::Arg(1, Ty::Uint(8)));
+    let f = FuncDef {
+        id: FuncId("f".to_owned()),
+        body: Expr::Switch(
+            Rc::new(Expr::BinaryOp(
+                BinOp::Eq,
+                Rc::new(x1.clone()),
+                Rc::new(Expr::Value(Value::Const(0, Ty::Uint(8)))),
+            )),
+            vec![Rc::new(Expr::Value(Value::Const(0, Ty::Bool)))],
+            vec![
+                Rc::new(Expr::Tuple(vec![
+                    Rc::new(x1),
+                    Rc::new(Expr::Value(Value::Const(1, Ty::Bool))),
+                ])),
+                Rc::new(Expr::Abort(pair.clone())),
+            ],
+        ),
+        ty: Ty::Func(vec![pair, Ty::Uint(8)], vec![]),
+    };
+    let g = FuncDef {
+        id: FuncId("g".to_owned()),
+        body: Expr::Tuple(Vec::new()),
+        ty: Ty::Func(vec![Ty::Tuple(Vec::new())], vec![]),
     };
+    let (f_id, g_id) = (f.id.clone(), g.id.clone());
+    let program = Program::new(vec![f, g], SymbolTable::default());
 
-    assert_eq!(sire_smt::CheckResult::Unsat, check_equality(&a, &b)?);
+    // Every solver accepted by `--solver`.
+    for &solver in &[Solver::Z3, Solver::Cvc4] {
+        assert_eq!(CheckResult::Sat, check_consistency(&program, &f_id, solver)?, "{}", solver);
+        assert_eq!(CheckResult::Sat, check_consistency(&program, &g_id, solver)?, "{}", solver);
+        assert_eq!(
+            Some(vec![Value::Const(0, Ty::Uint(8))]),
+            find_abort(&program, &f_id, solver)?,
+            "{}",
+            solver
+        );
+    }
 
     Ok(())
 }
@@ -0,0 +1,74 @@
+use std::rc::Rc;
+
+use sire_sir::*;
+use sire_smt::smtlib::{define_component, ToSmtlib};
+
+#[test]
+fn test_define_funs_rec() {
+    let funcs = parse_func_defs(
+        "(defun is_even[] (bool (uint 8))
+            (switch (= _1 (const (uint 8) 0))
+                ((const bool 1) -> (const bool 1))
+                (else -> (is_odd _1))))
+        (defun is_odd[] (bool (uint 8))
+            (switch (= _1 (const (uint 8) 0))
+                ((const bool 1) -> (const bool 0))
+                (else -> (is_even _1))))",
+    )
+    .unwrap();
+
+    let code = define_component(&funcs.iter().collect::<Vec<_>>());
+
+    assert_eq!(
+        code,
+        "(define-funs-rec ((is_even ((x1 (_ BitVec 8))) Bool) (is_odd ((x1 (_ BitVec 8))) Bool)) \
+         ((ite (= x1 (_ bv0 8)) true (is_odd x1)) (ite (= x1 (_ bv0 8)) false (is_even x1))))"
+    );
+}
+
+#[test]
+fn test_shared_subexpressions() {
+    let funcs = parse_func_defs("(defun f[] ((uint 8) (uint 8) (uint 8)) (* (+ _1 _2) (+ _1 _2)))")
+        .unwrap();
+
+    assert_eq!(funcs[0].body.to_smtlib(), "(let ((let@2 (bvadd x1 x2))) (bvmul let@2 let@2))");
+
+    // `x = x + x` 64 times: the tree has 2^64 leaves but only 65 distinct subexpressions.
+    let mut expr = Expr::Value(Value::Arg(1, Ty::Uint(8)));
+    for _ in 0..64 {
+        let operand = Rc::new(expr);
+        expr = Expr::BinaryOp(BinOp::Add, operand.clone(), operand);
+    }
+    assert!(expr.to_smtlib().len() < 5000);
+
+    expr.optimize();
+    let func = FuncDef {
+        id: FuncId("f".to_owned()),
+        body: expr,
+        ty: Ty::Func(vec![Ty::Uint(8), Ty::Uint(8)], vec![]),
+    };
+    let program = Program::new(vec![func], SymbolTable::default());
+    assert!(program.to_smtlib().len() < 5000);
+}
+
+#[test]
+fn test_abort() {
+    let program = Program::new(
+        parse_func_defs(
+            "(defun div[] ((uint 8) (uint 8) (uint 8))
+                (switch (= _2 (const (uint 8) 0))
+                    ((const bool 0) -> (/ _1 _2))
+                    (else -> (abort (uint 8)))))",
+        )
+        .unwrap(),
+        SymbolTable::default(),
+    );
+
+    let code = program.to_smtlib();
+    assert!(code.contains("(declare-const |abort (uint 8)| (_ BitVec 8))"));
+    assert!(code.contains("(ite (= x2 (_ bv0 8)) |abort (uint 8)| (bvudiv x1 x2))"));
+    assert!(code.contains(
+        "(define-fun div@failed ((x1 (_ BitVec 8)) (x2 (_ BitVec 8))) Bool \
+         (ite (= x2 (_ bv0 8)) true false))"
+    ));
+}
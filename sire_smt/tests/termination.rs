@@ -0,0 +1,44 @@
+use sire_sir::*;
+use sire_smt::termination::{check_termination, Termination};
+use sire_smt::Solver;
+
+#[test]
+fn test_termination() -> Result<(), Box<dyn std::error::Error>> {
+    let program = Program::new(
+        parse_func_defs(
+            "(defun sum[] ((uint 64) (uint 64) (uint 64))
+                (switch (> _2 (const (uint 64) 0))
+                    ((const bool 0) -> _1)
+                    (else -> (sum (+ _1 (const (uint 64) 1)) (- _2 (const (uint 64) 1))))))
+            (defun rec_dist[] ((int 32) (int 32) (int 32))
+                (switch (> _1 _2)
+                    ((const bool 0) -> (rec_dist _2 _1))
+                    (else -> (- _1 _2))))",
+        )
+        .unwrap(),
+        SymbolTable::default(),
+    );
+
+    let sum = FuncId("sum".to_owned());
+    let rec_dist = FuncId("rec_dist".to_owned());
+
+    assert_eq!(Termination::Decreasing(2), check_termination(&program, &sum, Solver::Z3)?);
+    // `rec_dist(x, x)` calls itself forever.
+    assert_eq!(Termination::Unproved, check_termination(&program, &rec_dist, Solver::Z3)?);
+
+    Ok(())
+}
+
+#[test]
+fn test_termination_inconsistent() -> Result<(), Box<dyn std::error::Error>> {
+    // The definition of `f` has no model, which must not make every call look decreasing.
+    let program = Program::new(
+        parse_func_defs("(defun f[] ((uint 8) (uint 8)) (+ (f _1) (const (uint 8) 1)))").unwrap(),
+        SymbolTable::default(),
+    );
+
+    let f = FuncId("f".to_owned());
+    assert_eq!(Termination::Unproved, check_termination(&program, &f, Solver::Z3)?);
+
+    Ok(())
+}